@@ -0,0 +1,200 @@
+# Deutsch
+
+language-name = Deutsch
+
+## Startmenü
+
+controls =
+    Mit den Pfeiltasten steuern
+    { $boost } halten für Boost, das leert die Ausdauerleiste
+    { $pause } drücken für Pause
+    { $rewind } halten zum Zurückspulen im Übungsmodus
+start-continue = { $key } drücken, um das gespeicherte Spiel fortzusetzen
+start-new-game = { $key } drücken zum Starten
+start-daily = { $key } drücken für die Tagesherausforderung ({ $date })
+start-daily-played = Tagesherausforderung { $date } gespielt, Punkte { $score }
+start-daily-practice = { $key } drücken, um das Tagesfeld zu üben
+start-editor = { $key } drücken, um ein Level zu bearbeiten
+start-stats = { $key } drücken für Statistiken
+start-achievements = { $key } drücken für Erfolge
+start-language = { $key } drücken, um die Sprache zu wechseln: { $language }
+
+## Pausenmenü
+
+pause-title = Pause
+pause-hint = { $up }/{ $down } zum Bewegen, { $select } zum Auswählen, { $resume } zum Fortsetzen
+pause-resume = Fortsetzen
+pause-restart = Neu starten
+pause-save-game = Spiel speichern
+pause-settings = Einstellungen
+pause-controls = Steuerung
+pause-quit = Zum Hauptmenü
+game-saved = Spiel gespeichert
+game-save-failed = Speichern fehlgeschlagen: { $error }
+settings-title = Einstellungen
+controls-title = Steuerung
+menu-back = Zurück
+setting-on = An
+setting-off = Aus
+setting-sound = Ton: { $state }
+setting-difficulty = Schwierigkeit (nächstes Spiel): { $difficulty }
+setting-food-count = Futter auf dem Feld: { $count }
+setting-portals = Portale (nächstes Spiel): { $count }
+setting-hazards = Gefahren (nächstes Spiel): { $state }
+setting-mode = Modus (nächstes Spiel): { $mode }
+setting-practice = Übungsmodus ({ $key } spult zurück): { $state }
+setting-language = Sprache: { $language }
+
+## Spielmodi und Schwierigkeiten
+
+mode-classic = Klassisch
+mode-time-attack = Zeitangriff
+mode-time-attack-budget = Zeitangriff ({ $ticks } Ticks, +{ $bonus } pro Futter)
+mode-level = Level
+difficulty-easy = Leicht
+difficulty-normal = Normal
+difficulty-hard = Schwer
+difficulty-insane = Wahnsinn
+
+## Im Spiel
+
+speed = { $difficulty }  Tempo { $level }
+combo = Kombo x{ $multiplier }
+time-attack-ticks = Noch { $ticks } Ticks
+power-up-ghost = Geist
+power-up-magnet = Magnet
+power-up-double-score = x2
+power-up-slow-motion = Langsam
+power-up-shield = Schild
+ghost-split = Geist { $split }
+
+## Spielende
+
+game-over = SPIELENDE
+result-win = GEWONNEN
+result-time-up = ZEIT UM
+result-lose = VERLOREN
+game-over-reason = { $reason } in Tick { $tick }
+game-over-unranked = Zurückgespult, Punkte zählen nicht
+game-over-restart = { $key } drücken für Neustart
+game-over-exit = { $key } drücken zum Beenden
+end-hit-wall = Gegen eine Wand
+end-bit-self = Selbst gebissen
+end-hit-obstacle = Gegen ein Hindernis
+end-timeout = Keine Zeit mehr
+end-board-full = Feld gefüllt
+
+## Level-Editor
+
+editor-help =
+    Linksklick malt, Rechtsklick radiert
+    { $tools }
+    { $shrink } und { $grow } ändern die Feldgröße
+    { $save } speichern, { $load } laden
+    { $play } Probespiel, { $back } zurück
+editor-title = Level-Editor
+editor-tool = Werkzeug: { $tool }
+editor-pending-portal = Das andere Ende des Portals anklicken
+editor-board = Feld: { $size }x{ $size }
+editor-start-doesnt-fit = Die Schlange passt dort nicht hin
+editor-no-portal-letters = Keine Portalbuchstaben mehr frei
+editor-saved = Gespeichert in { $path }
+editor-save-failed = Speichern fehlgeschlagen: { $error }
+editor-loaded = { $path } geladen
+editor-load-failed = Laden fehlgeschlagen: { $error }
+editor-tool-rock = Fels
+editor-tool-timed-wall = Zeitmauer
+editor-tool-lava = Lava
+editor-tool-portal = Portal
+editor-tool-start = Schlangenstart
+editor-tool-food-zone = Futterzone
+
+## Statistiken
+
+stats-session = Sitzung
+stats-lifetime = Insgesamt
+stats-games-played = Gespielte Spiele
+stats-wins = Siege
+stats-lost-to-wall = An Wand verloren
+stats-lost-to-own-body = An sich selbst verloren
+stats-lost-to-obstacle = An Hindernis verloren
+stats-out-of-time = Zeit abgelaufen
+stats-food-eaten = Gefressenes Futter
+stats-longest-snake = Längste Schlange
+stats-best-score = Bestwert { $difficulty }
+stats-average-ticks = Ticks im Schnitt
+stats-time-boosting = Zeit im Boost
+stats-time-played = Gespielte Zeit
+stats-charts =
+    Oberes Diagramm: die letzten { $count } Punktzahlen
+    Unteres Diagramm: wie Spiele endeten
+go-back = { $key } oder { $select } drücken, um zurückzugehen
+
+## Erfolge
+
+achievements-title = Erfolge { $unlocked }/{ $total }
+achievement-unlocked = Erfolg freigeschaltet: { $name }
+achievement-first_bite = Erster Bissen
+    .description = Ein Stück Futter fressen
+achievement-long_snake = Lange Schlange
+    .description = Länge 50 erreichen
+achievement-board_full = Volles Feld
+    .description = Ein Feld gewinnen
+achievement-steady = Langsam, aber sicher
+    .description = 10 Futter hintereinander ohne Boost fressen
+achievement-survivor = Überlebender
+    .description = 5 Minuten in einem Spiel überleben
+achievement-combo = Kombobrecher
+    .description = Eine Kombo von 5 schaffen
+achievement-high_score = Großverdiener
+    .description = 10000 Punkte in einem Spiel erreichen
+achievement-golden = Goldener Bissen
+    .description = Ein Bonusfutter fressen
+achievement-ouch = Autsch
+    .description = Ein Spiel verlieren
+
+## Versus
+
+versus-waiting = Warte auf einen Gegner
+versus-joining = Trete bei
+versus-out-of-sync = Bei Tick { $tick } nicht mehr synchron, Spiel abgebrochen
+versus-disconnected = Gegner getrennt
+versus-opponent = Gegner { $score } ({ $split })
+versus-draw = Unentschieden
+versus-won = Gewonnen mit { $points } Punkten Vorsprung
+versus-lost = Verloren mit { $points } Punkten Rückstand
+
+## Bestenliste
+
+leaderboard = Bestenliste
+leaderboard-new-best = Neuer Bestwert, Platz { $rank } von { $total } in { $mode } { $difficulty }
+leaderboard-best-stays = Dein Bestwert bleibt Platz { $rank } von { $total } in { $mode } { $difficulty }
+leaderboard-unsendable = Punkte konnten nicht gesendet werden: { $error }
+leaderboard-unreachable = { $address } nicht erreichbar: { $error }
+leaderboard-turned-down = Punkte abgelehnt, { $reason }
+leaderboard-bad-answer = Unlesbare Antwort von { $address }: { $error }
+leaderboard-top = { $mode } { $difficulty }, { $total } Spieler
+reject-unreadable = der Server konnte sie nicht lesen
+reject-no-player-name = der Spieler hat keinen Namen
+reject-seed-mismatch = der Seed passt nicht zur Aufzeichnung
+reject-wrong-mode = die Aufzeichnung ist kein Spiel im Modus { $mode }
+reject-too-long = die Aufzeichnung ist zu lang
+reject-events-out-of-order = die Ereignisse der Aufzeichnung sind durcheinander
+reject-broken-level = das Level ist fehlerhaft
+reject-unranked-rules = das Spiel lief nicht mit den Standardregeln
+reject-boosts-past-stamina = die Aufzeichnung boostet in Tick { $tick } ohne Ausdauer
+reject-unfinished-game = die Aufzeichnung endet nicht mit dem Spiel
+reject-wrong-score = die Aufzeichnung erreicht { $replayed } Punkte, nicht { $submitted }
+
+## Zuschauen
+
+spectate-viewers = { $count } schauen zu
+viewer-stream-closed = Das Spiel hat die Übertragung beendet
+viewer-waiting = Warte auf das Spiel
+viewer-food-eaten = Futter gefressen ({ $food }), Punkte { $score }
+food-normal = normal
+food-golden = golden
+food-shrink = schrumpfen
+food-speed = schneller
+food-slow = langsamer
+food-bonus = Bonus
@@ -0,0 +1,203 @@
+# English, the language every other file is translated from
+#
+# Key names, scores and file paths are passed in as variables. Messages missing from another
+# language fall back to the ones here.
+
+language-name = English
+
+## Start menu
+
+controls =
+    Move with arrows keys
+    Hold { $boost } to boost, it drains the stamina bar
+    Press { $pause } to pause
+    Hold { $rewind } to rewind in practice mode
+start-continue = Press { $key } to continue your saved game
+start-new-game = Press { $key } to start
+start-daily = Press { $key } for the daily challenge ({ $date })
+start-daily-played = Daily { $date } played, score { $score }
+start-daily-practice = Press { $key } to practice the daily board
+start-editor = Press { $key } to edit a level
+start-stats = Press { $key } for statistics
+start-achievements = Press { $key } for achievements
+start-language = Press { $key } to change the language: { $language }
+
+## Pause menu
+
+pause-title = Paused
+pause-hint = { $up }/{ $down } to move, { $select } to select, { $resume } to resume
+pause-resume = Resume
+pause-restart = Restart
+pause-save-game = Save Game
+pause-settings = Settings
+pause-controls = Controls
+pause-quit = Quit to Main Menu
+game-saved = Game saved
+game-save-failed = Couldn't save: { $error }
+settings-title = Settings
+controls-title = Controls
+menu-back = Back
+setting-on = On
+setting-off = Off
+setting-sound = Sound: { $state }
+setting-difficulty = Difficulty (next game): { $difficulty }
+setting-food-count = Food on board: { $count }
+setting-portals = Portals (next game): { $count }
+setting-hazards = Hazards (next game): { $state }
+setting-mode = Mode (next game): { $mode }
+setting-practice = Practice mode ({ $key } rewinds): { $state }
+setting-language = Language: { $language }
+
+## Game modes and difficulties
+
+mode-classic = Classic
+mode-time-attack = Time Attack
+mode-time-attack-budget = Time Attack ({ $ticks } ticks, +{ $bonus } per food)
+mode-level = Level
+difficulty-easy = Easy
+difficulty-normal = Normal
+difficulty-hard = Hard
+difficulty-insane = Insane
+
+## While playing
+
+speed = { $difficulty }  Speed { $level }
+combo = Combo x{ $multiplier }
+time-attack-ticks = { $ticks } ticks left
+power-up-ghost = Ghost
+power-up-magnet = Magnet
+power-up-double-score = x2
+power-up-slow-motion = Slow
+power-up-shield = Shield
+ghost-split = Ghost { $split }
+
+## Game over
+
+game-over = GAME OVER
+result-win = WIN
+result-time-up = TIME UP
+result-lose = LOSE
+game-over-reason = { $reason } on tick { $tick }
+game-over-unranked = Rewind used, score not ranked
+game-over-restart = Press { $key } to restart
+game-over-exit = Press { $key } to exit
+end-hit-wall = Hit a wall
+end-bit-self = Bit itself
+end-hit-obstacle = Hit an obstacle
+end-timeout = Out of time
+end-board-full = Filled the board
+
+## Level editor
+
+editor-help =
+    Left click paints, right click erases
+    { $tools }
+    { $shrink } and { $grow } resize the board
+    { $save } save, { $load } load
+    { $play } test play, { $back } back
+editor-title = Level editor
+editor-tool = Tool: { $tool }
+editor-pending-portal = Click the other end of the portal
+editor-board = Board: { $size }x{ $size }
+editor-start-doesnt-fit = The snake doesn't fit there
+editor-no-portal-letters = No portal letters left
+editor-saved = Saved to { $path }
+editor-save-failed = Couldn't save: { $error }
+editor-loaded = Loaded { $path }
+editor-load-failed = Couldn't load: { $error }
+editor-tool-rock = Rock
+editor-tool-timed-wall = Timed wall
+editor-tool-lava = Lava
+editor-tool-portal = Portal
+editor-tool-start = Snake start
+editor-tool-food-zone = Food zone
+
+## Statistics
+
+stats-session = Session
+stats-lifetime = Lifetime
+stats-games-played = Games played
+stats-wins = Wins
+stats-lost-to-wall = Lost to a wall
+stats-lost-to-own-body = Lost to own body
+stats-lost-to-obstacle = Lost to an obstacle
+stats-out-of-time = Out of time
+stats-food-eaten = Food eaten
+stats-longest-snake = Longest snake
+stats-best-score = Best score { $difficulty }
+stats-average-ticks = Average ticks alive
+stats-time-boosting = Time boosting
+stats-time-played = Time played
+stats-charts =
+    Top chart: last { $count } scores
+    Bottom chart: how games ended
+go-back = Press { $key } or { $select } to go back
+
+## Achievements
+
+achievements-title = Achievements { $unlocked }/{ $total }
+achievement-unlocked = Achievement unlocked: { $name }
+achievement-first_bite = First Bite
+    .description = Eat a piece of food
+achievement-long_snake = Long Snake
+    .description = Reach length 50
+achievement-board_full = Board Full
+    .description = Win a board
+achievement-steady = Slow and Steady
+    .description = Eat 10 food in a row without boosting
+achievement-survivor = Survivor
+    .description = Survive 5 minutes in one game
+achievement-combo = Combo Breaker
+    .description = Chain a combo of 5
+achievement-high_score = High Roller
+    .description = Score 10000 in one game
+achievement-golden = Golden Bite
+    .description = Eat a bonus food
+achievement-ouch = Ouch
+    .description = Lose a game
+
+## Versus
+
+versus-waiting = Waiting for an opponent
+versus-joining = Joining
+versus-out-of-sync = Out of sync at tick { $tick }, match abandoned
+versus-disconnected = Opponent disconnected
+versus-opponent = Opponent { $score } ({ $split })
+versus-draw = Draw
+versus-won = You win by { $points }
+versus-lost = You lose by { $points }
+
+## Leaderboard
+
+leaderboard = Leaderboard
+leaderboard-new-best = New best, #{ $rank } of { $total } on { $mode } { $difficulty }
+leaderboard-best-stays = Your best stays #{ $rank } of { $total } on { $mode } { $difficulty }
+leaderboard-unsendable = Couldn't send the score: { $error }
+leaderboard-unreachable = Couldn't reach { $address }: { $error }
+leaderboard-turned-down = Score turned down, { $reason }
+leaderboard-bad-answer = Unreadable answer from { $address }: { $error }
+leaderboard-top = { $mode } { $difficulty }, { $total } players
+reject-unreadable = the server couldn't read it
+reject-no-player-name = the player has no name
+reject-seed-mismatch = the seed is not the one of the replay
+reject-wrong-mode = the replay is not a { $mode } game
+reject-too-long = the replay is too long
+reject-events-out-of-order = the replay events are out of order
+reject-broken-level = the level is broken
+reject-unranked-rules = the game wasn't played with the standard rules
+reject-boosts-past-stamina = the replay boosts past the stamina on tick { $tick }
+reject-unfinished-game = the replay doesn't end with the game
+reject-wrong-score = the replay scores { $replayed }, not { $submitted }
+
+## Spectating
+
+spectate-viewers = { $count } watching
+viewer-stream-closed = The game closed the stream
+viewer-waiting = Waiting for the game
+viewer-food-eaten = Ate { $food } food, score { $score }
+food-normal = normal
+food-golden = golden
+food-shrink = shrink
+food-speed = speed
+food-slow = slow
+food-bonus = bonus
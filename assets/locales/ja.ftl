@@ -0,0 +1,200 @@
+# 日本語
+
+language-name = 日本語
+
+## スタートメニュー
+
+controls =
+    矢印キーで移動
+    { $boost } 長押しでブースト、スタミナを消費します
+    { $pause } で一時停止
+    練習モードでは { $rewind } 長押しで巻き戻し
+start-continue = { $key } で保存したゲームを続ける
+start-new-game = { $key } でスタート
+start-daily = { $key } でデイリーチャレンジ（{ $date }）
+start-daily-played = デイリー { $date } プレイ済み、スコア { $score }
+start-daily-practice = { $key } でデイリーの盤面を練習
+start-editor = { $key } でレベルを編集
+start-stats = { $key } で統計
+start-achievements = { $key } で実績
+start-language = { $key } で言語を変更：{ $language }
+
+## 一時停止メニュー
+
+pause-title = 一時停止
+pause-hint = { $up }/{ $down } で移動、{ $select } で決定、{ $resume } で再開
+pause-resume = 再開
+pause-restart = やり直す
+pause-save-game = ゲームを保存
+pause-settings = 設定
+pause-controls = 操作方法
+pause-quit = メインメニューへ
+game-saved = ゲームを保存しました
+game-save-failed = 保存できません：{ $error }
+settings-title = 設定
+controls-title = 操作方法
+menu-back = 戻る
+setting-on = オン
+setting-off = オフ
+setting-sound = サウンド：{ $state }
+setting-difficulty = 難易度（次のゲーム）：{ $difficulty }
+setting-food-count = 盤面のエサ：{ $count }
+setting-portals = ポータル（次のゲーム）：{ $count }
+setting-hazards = 障害物（次のゲーム）：{ $state }
+setting-mode = モード（次のゲーム）：{ $mode }
+setting-practice = 練習モード（{ $key } で巻き戻し）：{ $state }
+setting-language = 言語：{ $language }
+
+## ゲームモードと難易度
+
+mode-classic = クラシック
+mode-time-attack = タイムアタック
+mode-time-attack-budget = タイムアタック（{ $ticks } ティック、エサ1個で +{ $bonus }）
+mode-level = レベル
+difficulty-easy = やさしい
+difficulty-normal = ふつう
+difficulty-hard = むずかしい
+difficulty-insane = 鬼
+
+## プレイ中
+
+speed = { $difficulty }  スピード { $level }
+combo = コンボ x{ $multiplier }
+time-attack-ticks = 残り { $ticks } ティック
+power-up-ghost = ゴースト
+power-up-magnet = マグネット
+power-up-double-score = x2
+power-up-slow-motion = スロー
+power-up-shield = シールド
+ghost-split = ゴースト { $split }
+
+## ゲームオーバー
+
+game-over = ゲームオーバー
+result-win = 勝ち
+result-time-up = タイムアップ
+result-lose = 負け
+game-over-reason = { $tick } ティック目に{ $reason }
+game-over-unranked = 巻き戻しを使ったため記録されません
+game-over-restart = { $key } でやり直す
+game-over-exit = { $key } で終了
+end-hit-wall = 壁にぶつかった
+end-bit-self = 自分をかんだ
+end-hit-obstacle = 障害物にぶつかった
+end-timeout = 時間切れ
+end-board-full = 盤面を埋めた
+
+## レベルエディター
+
+editor-help =
+    左クリックで描く、右クリックで消す
+    { $tools }
+    { $shrink } と { $grow } で盤面の大きさを変更
+    { $save } で保存、{ $load } で読み込み
+    { $play } でテストプレイ、{ $back } で戻る
+editor-title = レベルエディター
+editor-tool = ツール：{ $tool }
+editor-pending-portal = ポータルのもう一方の端をクリック
+editor-board = 盤面：{ $size }x{ $size }
+editor-start-doesnt-fit = ヘビがそこに収まりません
+editor-no-portal-letters = ポータルの文字が残っていません
+editor-saved = { $path } に保存しました
+editor-save-failed = 保存できません：{ $error }
+editor-loaded = { $path } を読み込みました
+editor-load-failed = 読み込めません：{ $error }
+editor-tool-rock = 岩
+editor-tool-timed-wall = 時限の壁
+editor-tool-lava = 溶岩
+editor-tool-portal = ポータル
+editor-tool-start = スタート位置
+editor-tool-food-zone = エサの範囲
+
+## 統計
+
+stats-session = 今回
+stats-lifetime = 通算
+stats-games-played = プレイ回数
+stats-wins = 勝利
+stats-lost-to-wall = 壁で負け
+stats-lost-to-own-body = 自分の体で負け
+stats-lost-to-obstacle = 障害物で負け
+stats-out-of-time = 時間切れ
+stats-food-eaten = 食べたエサ
+stats-longest-snake = 最長のヘビ
+stats-best-score = ベストスコア { $difficulty }
+stats-average-ticks = 平均生存ティック
+stats-time-boosting = ブースト時間
+stats-time-played = プレイ時間
+stats-charts =
+    上のグラフ：直近 { $count } 回のスコア
+    下のグラフ：ゲームの終わり方
+go-back = { $key } か { $select } で戻る
+
+## 実績
+
+achievements-title = 実績 { $unlocked }/{ $total }
+achievement-unlocked = 実績解除：{ $name }
+achievement-first_bite = はじめの一口
+    .description = エサを1つ食べる
+achievement-long_snake = 長いヘビ
+    .description = 長さ50に達する
+achievement-board_full = 満杯
+    .description = 盤面を埋めて勝つ
+achievement-steady = ゆっくり着実に
+    .description = ブーストせずにエサを10個続けて食べる
+achievement-survivor = サバイバー
+    .description = 1ゲームで5分生き残る
+achievement-combo = コンボブレイカー
+    .description = 5コンボをつなげる
+achievement-high_score = ハイローラー
+    .description = 1ゲームで10000点を取る
+achievement-golden = 黄金の一口
+    .description = ボーナスのエサを食べる
+achievement-ouch = いたっ
+    .description = ゲームに負ける
+
+## 対戦
+
+versus-waiting = 対戦相手を待っています
+versus-joining = 参加中
+versus-out-of-sync = { $tick } ティック目で同期がずれたため中止しました
+versus-disconnected = 対戦相手が切断しました
+versus-opponent = 相手 { $score }（{ $split }）
+versus-draw = 引き分け
+versus-won = { $points } 点差で勝ち
+versus-lost = { $points } 点差で負け
+
+## ランキング
+
+leaderboard = ランキング
+leaderboard-new-best = 自己ベスト更新、{ $mode } { $difficulty } で { $total } 人中 { $rank } 位
+leaderboard-best-stays = 自己ベストは { $mode } { $difficulty } で { $total } 人中 { $rank } 位のまま
+leaderboard-unsendable = スコアを送信できません：{ $error }
+leaderboard-unreachable = { $address } に接続できません：{ $error }
+leaderboard-turned-down = スコアは認められませんでした、{ $reason }
+leaderboard-bad-answer = { $address } の応答が読めません：{ $error }
+leaderboard-top = { $mode } { $difficulty }、{ $total } 人
+reject-unreadable = サーバーが読み取れませんでした
+reject-no-player-name = プレイヤー名がありません
+reject-seed-mismatch = シードがリプレイと一致しません
+reject-wrong-mode = リプレイは{ $mode }のゲームではありません
+reject-too-long = リプレイが長すぎます
+reject-events-out-of-order = リプレイのイベントの順番が正しくありません
+reject-broken-level = レベルが壊れています
+reject-unranked-rules = 標準ルールでプレイされていません
+reject-boosts-past-stamina = ティック { $tick } でスタミナ切れなのにブーストしています
+reject-unfinished-game = リプレイがゲームの終わりまでありません
+reject-wrong-score = リプレイのスコアは { $replayed } で、{ $submitted } ではありません
+
+## 観戦
+
+spectate-viewers = 観戦者 { $count }人
+viewer-stream-closed = ゲームが配信を終了しました
+viewer-waiting = ゲームを待っています
+viewer-food-eaten = エサを食べた（{ $food }）、スコア { $score }
+food-normal = ふつう
+food-golden = 金
+food-shrink = 縮む
+food-speed = 速い
+food-slow = 遅い
+food-bonus = ボーナス
@@ -0,0 +1,185 @@
+use std::{collections::{BTreeSet, VecDeque}, fs, io, time::Duration};
+use bevy::prelude::Resource;
+
+use crate::{
+    Sze,
+    FoodEaten,
+    SimulationOver,
+    SimulationTicked,
+    food::FoodKind,
+    locale::Locale,
+    simulation::START_SNAKE_LENGHT
+};
+
+const ACHIEVEMENTS_DIR: &str = "saves";
+const ACHIEVEMENTS_PATH: &str = "saves/achievements.ron";
+
+pub static ACHIEVEMENTS: [Achievement; 9] = [
+    Achievement { id: "first_bite", condition: Condition::EatFood(1) },
+    Achievement { id: "long_snake", condition: Condition::ReachLength(50) },
+    Achievement { id: "board_full", condition: Condition::Win },
+    Achievement { id: "steady", condition: Condition::EatFoodWithoutBoosting(10) },
+    Achievement { id: "survivor", condition: Condition::SurviveFor(Duration::from_secs(300)) },
+    Achievement { id: "combo", condition: Condition::ComboChain(5) },
+    Achievement { id: "high_score", condition: Condition::ReachScore(10000) },
+    Achievement { id: "golden", condition: Condition::EatFoodKind(FoodKind::Bonus) },
+    Achievement { id: "ouch", condition: Condition::Lose }
+];
+
+/// The name and description are the `achievement-<id>` message and its `.description`
+pub struct Achievement {
+    /// Written to the save file, never change it once released
+    pub id: &'static str,
+    pub condition: Condition
+}
+
+/// What has to happen in a single game to unlock an achievement
+pub enum Condition {
+    EatFood(Sze),
+    EatFoodKind(FoodKind),
+    /// Food eaten since the last tick spent boosting
+    EatFoodWithoutBoosting(Sze),
+    ReachLength(Sze),
+    ReachScore(Sze),
+    ComboChain(Sze),
+    /// Play time, pauses don't count
+    SurviveFor(Duration),
+    Win,
+    Lose
+}
+
+/// Follows the current game through its events and unlocks achievements as their conditions are met
+///
+/// Unlocks are saved right away and queued as toasts for the HUD.
+#[derive(Resource, Default)]
+pub struct AchievementTracker {
+    unlocked: BTreeSet<String>,
+    toasts: VecDeque<&'static Achievement>,
+    game: GameProgress
+}
+
+/// Progress of the current game that the events don't carry themselves
+#[derive(Default)]
+struct GameProgress {
+    seed: Option<u64>,
+    last_tick: Sze,
+    /// Not counting the pieces the snake started with, like `Condition::EatFood`
+    food_at_last_boost: Sze,
+    play_time: Duration
+}
+
+impl Achievement {
+    pub fn get_name(&self, locale: &Locale) -> String { locale.get(&format!("achievement-{}", self.id)) }
+
+    pub fn get_description(&self, locale: &Locale) -> String {
+        locale.get_attribute(&format!("achievement-{}", self.id), "description")
+    }
+}
+
+impl AchievementTracker {
+    /// Starts with nothing unlocked if there is no save or it can't be read
+    pub fn load() -> Self {
+        let unlocked = match fs::read_to_string(ACHIEVEMENTS_PATH) {
+            Ok(contents) => ron::from_str(&contents).unwrap_or_else(| error | {
+                eprintln!("Ignoring unreadable achievements in {}: {}", ACHIEVEMENTS_PATH, error);
+                BTreeSet::new()
+            }),
+            Err(_) => BTreeSet::new()
+        };
+        AchievementTracker { unlocked, ..Default::default() }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let contents = ron::ser::to_string_pretty(&self.unlocked, ron::ser::PrettyConfig::default())
+            .map_err(| error | io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::create_dir_all(ACHIEVEMENTS_DIR)?;
+        fs::write(ACHIEVEMENTS_PATH, contents)
+    }
+
+    pub fn is_unlocked(&self, achievement: &Achievement) -> bool { self.unlocked.contains(achievement.id) }
+
+    pub fn get_unlocked_count(&self) -> usize {
+        ACHIEVEMENTS.iter().filter(| achievement | self.is_unlocked(achievement)).count()
+    }
+
+    /// The next unlock to show, oldest first
+    pub fn pop_toast(&mut self) -> Option<&'static Achievement> { self.toasts.pop_front() }
+
+    pub fn add_play_time(&mut self, delta: Duration) {
+        self.game.play_time += delta;
+        let play_time = self.game.play_time;
+        self.unlock_where(| condition | matches!(condition, Condition::SurviveFor(duration) if play_time >= *duration));
+    }
+
+    pub fn on_tick(&mut self, event: &SimulationTicked) {
+        // A new game, a restart on the same seed, or a rewind which is treated the same
+        if self.game.seed != Some(event.seed) || event.tick <= self.game.last_tick {
+            self.game = GameProgress { seed: Some(event.seed), ..GameProgress::default() };
+        }
+        self.game.last_tick = event.tick;
+        let eaten_food = event.eaten_food.saturating_sub(START_SNAKE_LENGHT as Sze);
+        if event.is_boosting {
+            self.game.food_at_last_boost = eaten_food;
+        }
+        let food_without_boosting = eaten_food.saturating_sub(self.game.food_at_last_boost);
+        self.unlock_where(| condition | match *condition {
+            Condition::EatFood(count) => eaten_food >= count,
+            Condition::EatFoodWithoutBoosting(count) => food_without_boosting >= count,
+            Condition::ReachLength(length) => event.snake_length >= length,
+            Condition::ReachScore(score) => event.score >= score,
+            Condition::ComboChain(chain) => event.combo_chain >= chain,
+            _ => false
+        });
+    }
+
+    pub fn on_food_eaten(&mut self, event: &FoodEaten) {
+        self.unlock_where(| condition | matches!(condition, Condition::EatFoodKind(kind) if *kind == event.food_kind));
+    }
+
+    pub fn on_game_over(&mut self, event: &SimulationOver) {
+        let is_win = event.reason.is_win();
+        self.unlock_where(| condition | match condition {
+            Condition::Win => is_win,
+            Condition::Lose => !is_win,
+            _ => false
+        });
+    }
+
+    fn unlock_where(&mut self, is_met: impl Fn(&Condition) -> bool) {
+        let mut has_new_unlocks = false;
+        for achievement in ACHIEVEMENTS.iter() {
+            if !self.is_unlocked(achievement) && is_met(&achievement.condition) {
+                self.unlocked.insert(achievement.id.to_string());
+                self.toasts.push_back(achievement);
+                has_new_unlocks = true;
+            }
+        }
+        if has_new_unlocks {
+            if let Err(error) = self.save() {
+                eprintln!("Couldn't save the achievements: {}", error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fresh_game_unlocks_nothing_on_first_tick() {
+        let mut tracker = AchievementTracker::default();
+        let start_length = START_SNAKE_LENGHT as Sze;
+        tracker.on_tick(&SimulationTicked {
+            seed: 7,
+            tick: 1,
+            score: start_length * 100,
+            snake_length: start_length,
+            eaten_food: start_length,
+            is_boosting: false,
+            combo_chain: 0
+        });
+        assert_eq!(tracker.get_unlocked_count(), 0);
+        assert!(tracker.pop_toast().is_none());
+    }
+}
@@ -1,26 +1,43 @@
 use bevy::prelude::Color;
+use serde::{Serialize, Deserialize};
 
 use crate::Sze;
+use crate::food::FoodKind;
+use crate::powerup::PowerUpKind;
+use crate::portal::get_portal_color;
+use crate::hazard::HazardKind;
 use crate::grid::GRID_SIZE;
+use crate::utils::Direction;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Cell {
     pub position: CellPos,
     pub content: CellContent
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum CellContent {
-    Food,
-    SnakeBody { age: Sze }
+    Food { kind: FoodKind, age: Sze },
+    PowerUp { kind: PowerUpKind, age: Sze },
+    SnakeBody { age: Sze },
+    Portal { pair_id: usize },
+    Hazard { kind: HazardKind }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+/// Deserializing fails for positions outside the board
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedCellPos")]
 pub struct CellPos {
     pub x: usize,
     pub y: usize
 }
 
+#[derive(Deserialize)]
+struct UncheckedCellPos {
+    x: usize,
+    y: usize
+}
+
 impl CellPos {
     pub fn get_neighbors(&self) -> Vec<CellPos> {
         let mut neighbors = vec!();
@@ -30,13 +47,46 @@ impl CellPos {
         if self.y < GRID_SIZE - 1 { neighbors.push(CellPos { x: self.x, y: self.y + 1 }) };
         neighbors
     }
+
+    /// The adjacent cell in `dir`, `None` if it would be outside the grid
+    pub fn step(&self, dir: Direction) -> Option<CellPos> {
+        let dir_vector = match dir {
+            Direction::Up => [0, 1],
+            Direction::Down => [0, -1],
+            Direction::Left => [-1, 0],
+            Direction::Right => [1, 0]
+        };
+        let ix = (self.x as i64) + dir_vector[0];
+        let iy = (self.y as i64) + dir_vector[1];
+        let grid_size = GRID_SIZE as i64;
+        if ix < 0 || iy < 0 || ix >= grid_size || iy >= grid_size {
+            None
+        } else {
+            Some(CellPos { x: ix as usize, y: iy as usize })
+        }
+    }
 }
 
 impl CellContent {
     pub fn get_color(&self) -> Color {
         match self {
-            Self::Food => Color::BLUE,
-            Self::SnakeBody { .. } => Color::BLACK
+            Self::Food { kind, .. } => kind.get_color(),
+            Self::PowerUp { kind, .. } => kind.get_color(),
+            Self::SnakeBody { .. } => Color::BLACK,
+            Self::Portal { pair_id } => get_portal_color(*pair_id),
+            Self::Hazard { kind } => kind.get_color()
+        }
+    }
+}
+
+impl TryFrom<UncheckedCellPos> for CellPos {
+    type Error = String;
+
+    fn try_from(pos: UncheckedCellPos) -> Result<Self, Self::Error> {
+        if pos.x < GRID_SIZE && pos.y < GRID_SIZE {
+            Ok(CellPos { x: pos.x, y: pos.y })
+        } else {
+            Err(format!("cell ({}, {}) is outside the board", pos.x, pos.y))
         }
     }
 }
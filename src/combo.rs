@@ -0,0 +1,48 @@
+use serde::{Serialize, Deserialize};
+
+use crate::Sze;
+
+/// Ticks the next food has to be eaten in to keep the chain going
+pub const COMBO_WINDOW_TICKS: Sze = 16;
+/// Added to the window when the food was eaten while boosting
+pub const COMBO_BOOST_WINDOW_TICKS: Sze = 8;
+/// Points are multiplied by the chain length up to this
+pub const MAX_COMBO_MULTIPLIER: Sze = 5;
+
+/// Food eaten in quick succession, counted in ticks so replays build the same chains
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Combo {
+    /// Food in the chain, 0 before any food was eaten
+    chain: Sze,
+    last_food_tick: Sze,
+    window_ticks: Sze
+}
+
+impl Combo {
+    /// Grows the chain if the food came in time or starts a new one, returns the multiplier it scores with
+    pub fn add_food(&mut self, tick: Sze, is_boosting: bool) -> Sze {
+        self.chain = if self.is_active(tick) { self.chain + 1 } else { 1 };
+        self.last_food_tick = tick;
+        self.window_ticks = COMBO_WINDOW_TICKS + if is_boosting { COMBO_BOOST_WINDOW_TICKS } else { 0 };
+        self.get_multiplier(tick)
+    }
+
+    /// 0 once the window ran out
+    pub fn get_chain(&self, tick: Sze) -> Sze {
+        if self.is_active(tick) { self.chain } else { 0 }
+    }
+
+    pub fn get_multiplier(&self, tick: Sze) -> Sze { self.get_chain(tick).clamp(1, MAX_COMBO_MULTIPLIER) }
+
+    /// Share of the window left, from 1 right after eating down to 0
+    pub fn get_remaining_fraction(&self, tick: Sze) -> f32 {
+        if !self.is_active(tick) {
+            return 0.0;
+        }
+        1.0 - (tick - self.last_food_tick) as f32 / (self.window_ticks + 1) as f32
+    }
+
+    fn is_active(&self, tick: Sze) -> bool {
+        self.chain > 0 && tick.saturating_sub(self.last_food_tick) <= self.window_ticks
+    }
+}
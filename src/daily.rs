@@ -0,0 +1,239 @@
+use std::{fmt, fs, io, path::PathBuf, time::{SystemTime, UNIX_EPOCH}};
+use bevy::prelude::Resource;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    Sze,
+    SimulationOver,
+    difficulty::Difficulty,
+    cell::CellPos,
+    food::{FoodConfig, FoodKind, FOOD_KINDS},
+    grid::GRID_SIZE,
+    hazard::HazardConfig,
+    input::DAILY_GAME_KEY,
+    level::{Level, MIN_PLAYABLE_SIZE, get_border_width, get_ring},
+    locale::Locale,
+    powerup::PowerUpConfig,
+    replay::Replay,
+    rules::{GameRules, TimeAttackRules},
+    simulation::{Sim, EndReason, START_SNAKE_LENGHT}
+};
+
+const DAILY_RESULTS_DIR: &str = "saves/daily";
+/// Keeps the rules from being picked with the same random sequence the simulation gets from the date
+const RULES_SEED_SALT: u64 = 0x5eed_da11;
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+/// Walled off boards start a size up from the editor's smallest, that one is too cramped for hazards
+const MIN_DAILY_PLAYABLE_SIZE: usize = MIN_PLAYABLE_SIZE + 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DailyDate {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum DailyMode { #[default] Off, Attempt, Practice }
+
+/// The daily challenge the player is in, if any
+///
+/// Every day gets one attempt, its result is saved as soon as it starts so quitting half way
+/// still uses it up. Practice games are played on the same board but never saved.
+#[derive(Resource, Default)]
+pub struct DailySession {
+    mode: DailyMode,
+    date: Option<DailyDate>,
+    /// The rules to go back to once the player leaves the daily challenge
+    saved_rules: Option<GameRules>
+}
+
+/// How a daily attempt went, with everything needed to replay it, written as RON to `DAILY_RESULTS_DIR`
+///
+/// The replay keeps its events too, boosting and food count changes play into the score.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub date: DailyDate,
+    /// `None` if the attempt was abandoned before the game ended
+    pub outcome: Option<EndReason>,
+    pub score: Sze,
+    pub replay: Replay
+}
+
+impl DailyDate {
+    pub fn today_utc() -> Self {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(| time | time.as_secs()).unwrap_or(0);
+        Self::from_days_since_epoch((seconds / SECONDS_PER_DAY) as i64)
+    }
+
+    /// Civil calendar conversion from http://howardhinnant.github.io/date_algorithms.html
+    fn from_days_since_epoch(days: i64) -> Self {
+        let shifted_days = days + 719_468;
+        let era = shifted_days.div_euclid(146_097);
+        let day_of_era = shifted_days.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+        let month = (if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 }) as u32;
+        let year = (year_of_era + era * 400) as i32 + if month <= 2 { 1 } else { 0 };
+        DailyDate { year, month, day }
+    }
+
+    /// `20261018` for the 18th of October 2026
+    pub fn get_seed(&self) -> u64 { self.year as u64 * 10_000 + self.month as u64 * 100 + self.day as u64 }
+
+    /// The date `get_seed` gave `seed`, `None` if no date gives it
+    pub fn from_seed(seed: u64) -> Option<Self> {
+        let date = DailyDate { year: i32::try_from(seed / 10_000).ok()?, month: (seed / 100 % 100) as u32, day: (seed % 100) as u32 };
+        ((1..=12).contains(&date.month) && (1..=31).contains(&date.day)).then_some(date)
+    }
+
+    fn get_result_path(&self) -> PathBuf { PathBuf::from(DAILY_RESULTS_DIR).join(format!("{}.ron", self)) }
+}
+
+impl fmt::Display for DailyDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Picks the day's board size, food, portals, hazards and clock, the same for everyone playing on `date`
+pub fn get_daily_rules(date: DailyDate) -> GameRules {
+    let mut rng = ChaCha8Rng::seed_from_u64(date.get_seed() ^ RULES_SEED_SALT);
+    let mut food_config = FoodConfig { food_count: rng.gen_range(1..=3), ..FoodConfig::default() };
+    for kind in FOOD_KINDS.iter().filter(| kind | !matches!(kind, FoodKind::Normal | FoodKind::Bonus)) {
+        if rng.gen_bool(0.5) {
+            food_config.rules[*kind as usize].spawn_weight = 0;
+        }
+    }
+    let random_portal_pairs = rng.gen_range(0..=2);
+    let hazard_config = HazardConfig {
+        random_patrols: rng.gen_range(0..=2),
+        random_walls: rng.gen_range(0..=3),
+        random_lava: rng.gen_range(0..=1),
+        ..HazardConfig::default()
+    };
+    let time_attack = rng.gen_bool(0.5).then(TimeAttackRules::default);
+    let playable_size = MIN_DAILY_PLAYABLE_SIZE + 2 * rng.gen_range(0..=(GRID_SIZE - MIN_DAILY_PLAYABLE_SIZE) / 2);
+    GameRules {
+        food_config,
+        power_up_config: PowerUpConfig::default(),
+        hazard_config,
+        random_portal_pairs,
+        level: get_walled_off_level(playable_size, &mut rng),
+        time_attack,
+        difficulty: Difficulty::Normal
+    }
+}
+
+/// Walls off everything outside a centered `playable_size` square with rocks like the editor does,
+/// the snake starts inside it with room ahead
+fn get_walled_off_level<R: Rng>(playable_size: usize, rng: &mut R) -> Option<Level> {
+    if playable_size >= GRID_SIZE {
+        return None;
+    }
+    let border = get_border_width(playable_size);
+    let rocks = (0..GRID_SIZE)
+        .flat_map(| x | (0..GRID_SIZE).map(move | y | CellPos { x, y }))
+        .filter(| &pos | get_ring(pos) < border)
+        .collect();
+    let start = CellPos {
+        x: rng.gen_range((border + START_SNAKE_LENGHT - 1)..(border + playable_size / 2)),
+        y: rng.gen_range(border..(GRID_SIZE - border))
+    };
+    Some(Level { rocks, start: Some(start), ..Level::default() })
+}
+
+impl DailySession {
+    /// Switches the simulation to today's board, falls back to practice if today's attempt is used up
+    pub fn start(&mut self, sim: &mut Sim, practice: bool) {
+        let date = DailyDate::today_utc();
+        if self.saved_rules.is_none() {
+            self.saved_rules = Some(sim.get_rules().clone());
+        }
+        sim.set_rules(get_daily_rules(date));
+        sim.reset_with_seed(date.get_seed());
+        self.date = Some(date);
+        self.mode = if practice || DailyResult::exists(date) { DailyMode::Practice } else { DailyMode::Attempt };
+        if self.mode == DailyMode::Attempt {
+            self.save_attempt(sim, None);
+        }
+    }
+
+    /// Restores the rules the simulation had before the daily challenge started
+    pub fn leave(&mut self, sim: &mut Sim) {
+        self.abandon_attempt(sim);
+        if let Some(rules) = self.saved_rules.take() {
+            sim.set_rules(rules);
+        }
+        self.mode = DailyMode::Off;
+        self.date = None;
+    }
+
+    /// Starts over on the day's board, any later game is practice
+    pub fn reset_game(&mut self, sim: &mut Sim) {
+        self.abandon_attempt(sim);
+        if let Some(date) = self.date {
+            sim.reset_with_seed(date.get_seed());
+        }
+    }
+
+    pub fn finish_attempt(&mut self, sim: &Sim, game_over: SimulationOver) {
+        if self.mode == DailyMode::Attempt {
+            self.save_attempt(sim, Some(game_over.reason));
+            self.mode = DailyMode::Practice;
+        }
+    }
+
+    pub fn is_active(&self) -> bool { self.mode != DailyMode::Off }
+
+    pub fn is_attempt(&self) -> bool { self.mode == DailyMode::Attempt }
+
+    pub fn get_menu_description(&self, locale: &Locale) -> String {
+        let date = DailyDate::today_utc();
+        match DailyResult::load(date) {
+            Ok(result) => locale.format("start-daily-played", &[("date", date.to_string().into()), ("score", result.score.into())]),
+            Err(_) => locale.format("start-daily", &[("key", DAILY_GAME_KEY.str.into()), ("date", date.to_string().into())])
+        }
+    }
+
+    fn abandon_attempt(&mut self, sim: &Sim) {
+        if self.mode == DailyMode::Attempt {
+            self.save_attempt(sim, None);
+            self.mode = DailyMode::Practice;
+        }
+    }
+
+    fn save_attempt(&self, sim: &Sim, outcome: Option<EndReason>) {
+        let Some(date) = self.date else {
+            return;
+        };
+        let result = DailyResult {
+            date,
+            outcome,
+            score: sim.get_score(),
+            replay: sim.get_replay().clone()
+        };
+        if let Err(error) = result.save() {
+            eprintln!("Couldn't save the daily result for {}: {}", date, error);
+        }
+    }
+}
+
+impl DailyResult {
+    pub fn exists(date: DailyDate) -> bool { date.get_result_path().exists() }
+
+    pub fn load(date: DailyDate) -> io::Result<Self> {
+        let contents = fs::read_to_string(date.get_result_path())?;
+        ron::from_str(&contents).map_err(| error | io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let contents = ron::ser::to_string(self).map_err(| error | io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::create_dir_all(DAILY_RESULTS_DIR)?;
+        fs::write(self.date.get_result_path(), contents)
+    }
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+use serde::{Serialize, Deserialize};
+
+use crate::Sze;
+
+pub const DIFFICULTIES: [Difficulty; 4] = [
+    Difficulty::Easy,
+    Difficulty::Normal,
+    Difficulty::Hard,
+    Difficulty::Insane
+];
+
+/// Highest speed level considered, curves are expected to reach their floor long before
+const MAX_SPEED_LEVEL: Sze = 100;
+
+const EASY: DifficultyPreset = DifficultyPreset {
+    start_tick_seconds: 0.6,
+    min_tick_seconds: 0.15,
+    curve: SpeedCurve::ScoreBased { step_seconds: 0.05, score_per_level: 2000 }
+};
+
+const NORMAL: DifficultyPreset = DifficultyPreset {
+    start_tick_seconds: 0.5,
+    min_tick_seconds: 0.1,
+    curve: SpeedCurve::Linear { step_seconds: 0.06, food_per_level: 5 }
+};
+
+const HARD: DifficultyPreset = DifficultyPreset {
+    start_tick_seconds: 0.4,
+    min_tick_seconds: 0.08,
+    curve: SpeedCurve::Exponential { factor: 0.85, food_per_level: 4 }
+};
+
+const INSANE: DifficultyPreset = DifficultyPreset {
+    start_tick_seconds: 0.25,
+    min_tick_seconds: 0.06,
+    curve: SpeedCurve::Stepped { tick_seconds: &[0.25, 0.2, 0.16, 0.13, 0.1, 0.08, 0.06], food_per_level: 3 }
+};
+
+/// Picks how fast a game starts and speeds up, fixed when the game starts
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Difficulty { Easy, #[default] Normal, Hard, Insane }
+
+pub struct DifficultyPreset {
+    pub start_tick_seconds: f32,
+    pub min_tick_seconds: f32,
+    pub curve: SpeedCurve
+}
+
+/// How the tick interval shrinks as the speed level goes up, and what earns a level
+pub enum SpeedCurve {
+    /// Takes `step_seconds` off per level, a level every `food_per_level` pieces of food
+    Linear { step_seconds: f32, food_per_level: Sze },
+    /// Multiplies by `factor` per level, a level every `food_per_level` pieces of food
+    Exponential { factor: f32, food_per_level: Sze },
+    /// Reads the interval of every level from a table that starts at level 0, the last entry holds
+    /// for all levels past it
+    Stepped { tick_seconds: &'static [f32], food_per_level: Sze },
+    /// Takes `step_seconds` off per level, a level every `score_per_level` points
+    ScoreBased { step_seconds: f32, score_per_level: Sze }
+}
+
+impl Difficulty {
+    pub fn get_label(&self) -> &'static str {
+        match self {
+            Self::Easy => "Easy",
+            Self::Normal => "Normal",
+            Self::Hard => "Hard",
+            Self::Insane => "Insane"
+        }
+    }
+
+    pub fn get_message_id(&self) -> &'static str {
+        match self {
+            Self::Easy => "difficulty-easy",
+            Self::Normal => "difficulty-normal",
+            Self::Hard => "difficulty-hard",
+            Self::Insane => "difficulty-insane"
+        }
+    }
+
+    pub fn get_preset(&self) -> &'static DifficultyPreset {
+        match self {
+            Self::Easy => &EASY,
+            Self::Normal => &NORMAL,
+            Self::Hard => &HARD,
+            Self::Insane => &INSANE
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = DIFFICULTIES.iter().position(| difficulty | difficulty == self).unwrap_or(0);
+        DIFFICULTIES[(index + 1) % DIFFICULTIES.len()]
+    }
+}
+
+impl DifficultyPreset {
+    /// Tick interval at `level`, never below `min_tick_seconds`
+    pub fn get_tick_duration(&self, level: Sze) -> Duration {
+        let seconds = match self.curve {
+            SpeedCurve::Linear { step_seconds, .. } | SpeedCurve::ScoreBased { step_seconds, .. } => {
+                self.start_tick_seconds - step_seconds * level as f32
+            },
+            SpeedCurve::Exponential { factor, .. } => self.start_tick_seconds * factor.powi(level as i32),
+            SpeedCurve::Stepped { tick_seconds, .. } => {
+                tick_seconds.get(level as usize).or(tick_seconds.last()).copied().unwrap_or(self.start_tick_seconds)
+            }
+        };
+        Duration::from_secs_f32(seconds.max(self.min_tick_seconds))
+    }
+
+    /// The first level that reaches `min_tick_seconds`, going further wouldn't change anything
+    pub fn get_max_level(&self) -> Sze {
+        let min_duration = Duration::from_secs_f32(self.min_tick_seconds);
+        (0..MAX_SPEED_LEVEL).find(| &level | self.get_tick_duration(level) <= min_duration).unwrap_or(MAX_SPEED_LEVEL)
+    }
+
+    /// Levels the curve has handed out so far in a game, before speed and slow food
+    pub fn get_earned_levels(&self, pieces_eaten: Sze, score: Sze) -> Sze {
+        match self.curve {
+            SpeedCurve::Linear { food_per_level, .. }
+            | SpeedCurve::Exponential { food_per_level, .. }
+            | SpeedCurve::Stepped { food_per_level, .. } => pieces_eaten / food_per_level,
+            SpeedCurve::ScoreBased { score_per_level, .. } => score / score_per_level
+        }
+    }
+}
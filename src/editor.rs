@@ -0,0 +1,272 @@
+use std::{fs, path::PathBuf};
+use bevy::prelude::{Resource, Color};
+
+use crate::{
+    cell::CellPos,
+    food::FoodKind,
+    grid::GRID_SIZE,
+    hazard::{HazardKind, get_patrol_path},
+    level::{Level, MIN_PLAYABLE_SIZE, get_border_width, get_ring, is_valid_start},
+    locale::Locale,
+    portal::{PortalPair, get_portal_color},
+    simulation::Sim
+};
+
+pub const DEFAULT_EDITOR_LEVEL_PATH: &str = "levels/custom.txt";
+
+pub const EDITOR_TOOLS: [EditorTool; 6] = [
+    EditorTool::Rock,
+    EditorTool::TimedWall,
+    EditorTool::Lava,
+    EditorTool::Portal,
+    EditorTool::Start,
+    EditorTool::FoodZone
+];
+
+/// Portal tiles are the letters `a` to `z`
+const MAX_PORTAL_PAIRS: usize = 26;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditorTool { Rock, TimedWall, Lava, Portal, Start, FoodZone }
+
+/// The result of the last thing the editor was asked to do, shown under the board size
+#[derive(Debug, Default)]
+enum EditorStatus {
+    #[default]
+    None,
+    StartDoesntFit,
+    NoPortalLetters,
+    Saved,
+    SaveFailed(String),
+    Loaded,
+    LoadFailed(String)
+}
+
+/// A level being painted, and the level the game had before it was test played
+#[derive(Resource)]
+pub struct LevelEditor {
+    level: Level,
+    tool: EditorTool,
+    /// First end of a portal pair waiting for the second one
+    pending_portal: Option<CellPos>,
+    playable_size: usize,
+    path: PathBuf,
+    status: EditorStatus,
+    level_before_test_play: Option<Option<Level>>
+}
+
+impl EditorTool {
+    pub fn get_message_id(&self) -> &'static str {
+        match self {
+            Self::Rock => "editor-tool-rock",
+            Self::TimedWall => "editor-tool-timed-wall",
+            Self::Lava => "editor-tool-lava",
+            Self::Portal => "editor-tool-portal",
+            Self::Start => "editor-tool-start",
+            Self::FoodZone => "editor-tool-food-zone"
+        }
+    }
+}
+
+impl LevelEditor {
+    pub fn new(path: PathBuf) -> Self {
+        LevelEditor {
+            level: Level::default(),
+            tool: EditorTool::Rock,
+            pending_portal: None,
+            playable_size: GRID_SIZE,
+            path,
+            status: EditorStatus::None,
+            level_before_test_play: None
+        }
+    }
+
+    pub fn get_level(&self) -> &Level { &self.level }
+
+    pub fn select_tool(&mut self, tool: EditorTool) {
+        self.tool = tool;
+        self.pending_portal = None;
+    }
+
+    /// Places the current tool on `pos`, portals and the start only on a fresh click so dragging
+    /// doesn't keep moving them
+    pub fn paint(&mut self, pos: CellPos, is_new_click: bool) {
+        let is_placed_once = matches!(self.tool, EditorTool::Portal | EditorTool::Start);
+        if (is_placed_once && !is_new_click) || !self.is_playable(pos) {
+            return;
+        }
+        match self.tool {
+            EditorTool::Portal => self.paint_portal(pos),
+            EditorTool::Start => {
+                if is_valid_start(pos) {
+                    self.level.clear_tile(pos);
+                    self.level.start = Some(pos);
+                } else {
+                    self.status = EditorStatus::StartDoesntFit;
+                }
+            },
+            tool => {
+                self.level.clear_tile(pos);
+                match tool {
+                    EditorTool::Rock => self.level.rocks.push(pos),
+                    EditorTool::TimedWall => self.level.timed_walls.push(pos),
+                    EditorTool::Lava => self.level.lava.push(pos),
+                    _ => self.level.food_zones.push(pos)
+                }
+            }
+        }
+    }
+
+    pub fn erase(&mut self, pos: CellPos) {
+        if self.is_playable(pos) {
+            self.level.clear_tile(pos);
+            if self.pending_portal == Some(pos) {
+                self.pending_portal = None;
+            }
+        }
+    }
+
+    pub fn grow_board(&mut self) { self.set_playable_size((self.playable_size + 2).min(GRID_SIZE)); }
+
+    pub fn shrink_board(&mut self) { self.set_playable_size(self.playable_size.saturating_sub(2).max(MIN_PLAYABLE_SIZE)); }
+
+    pub fn save(&mut self) {
+        if let Some(dir) = self.path.parent() {
+            if let Err(error) = fs::create_dir_all(dir) {
+                self.status = EditorStatus::SaveFailed(error.to_string());
+                return;
+            }
+        }
+        self.status = match self.level.save(&self.path) {
+            Ok(()) => EditorStatus::Saved,
+            Err(error) => EditorStatus::SaveFailed(error.to_string())
+        };
+    }
+
+    pub fn load(&mut self) {
+        match Level::load(&self.path) {
+            Ok(level) => {
+                self.playable_size = get_playable_size(&level);
+                self.level = level;
+                self.pending_portal = None;
+                self.status = EditorStatus::Loaded;
+            },
+            Err(error) => self.status = EditorStatus::LoadFailed(error.to_string())
+        }
+    }
+
+    /// Puts the edited level in the simulation, `stop_test_play` brings the old one back
+    pub fn start_test_play(&mut self, sim: &mut Sim) {
+        if self.level_before_test_play.is_none() {
+            self.level_before_test_play = Some(sim.get_rules().level.clone());
+        }
+        sim.set_level(Some(self.level.clone()));
+    }
+
+    /// Returns true if a test play was running
+    pub fn stop_test_play(&mut self, sim: &mut Sim) -> bool {
+        match self.level_before_test_play.take() {
+            Some(level) => {
+                sim.set_level(level);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn get_description(&self, locale: &Locale) -> String {
+        let pending_portal = match self.pending_portal {
+            Some(_) => format!("\n{}", locale.get("editor-pending-portal")),
+            None => String::new()
+        };
+        let path = self.path.display().to_string();
+        let status = match &self.status {
+            EditorStatus::None => String::new(),
+            EditorStatus::StartDoesntFit => locale.get("editor-start-doesnt-fit"),
+            EditorStatus::NoPortalLetters => locale.get("editor-no-portal-letters"),
+            EditorStatus::Saved => locale.format("editor-saved", &[("path", path.as_str().into())]),
+            EditorStatus::SaveFailed(error) => locale.format("editor-save-failed", &[("error", error.as_str().into())]),
+            EditorStatus::Loaded => locale.format("editor-loaded", &[("path", path.as_str().into())]),
+            EditorStatus::LoadFailed(error) => locale.format("editor-load-failed", &[("error", error.as_str().into())])
+        };
+        format!(
+            "{}\n\n{}{}\n{}\n\n{}\n{}",
+            locale.get("editor-title"),
+            locale.format("editor-tool", &[("tool", locale.get(self.tool.get_message_id()).into())]),
+            pending_portal,
+            locale.format("editor-board", &[("size", self.playable_size.into())]),
+            path,
+            status
+        )
+    }
+
+    pub fn get_tile_colors(&self) -> Vec<(CellPos, Color)> {
+        let mut tiles: Vec<(CellPos, Color)> = vec!();
+        for waypoints in self.level.patrols.iter() {
+            tiles.extend(get_patrol_path(waypoints).into_iter().map(| pos | (pos, HazardKind::Block.get_color())));
+        }
+        tiles.extend(self.level.food_zones.iter().map(| &pos | (pos, FoodKind::Normal.get_color().with_a(0.3))));
+        tiles.extend(self.level.rocks.iter().map(| &pos | (pos, HazardKind::Rock.get_color())));
+        tiles.extend(self.level.timed_walls.iter().map(| &pos | (pos, HazardKind::Wall.get_color())));
+        tiles.extend(self.level.lava.iter().map(| &pos | (pos, HazardKind::Lava.get_color())));
+        for (pair_id, pair) in self.level.portals.iter().enumerate() {
+            tiles.push((pair.a, get_portal_color(pair_id)));
+            tiles.push((pair.b, get_portal_color(pair_id)));
+        }
+        tiles.extend(self.pending_portal.map(| pos | (pos, get_portal_color(self.level.portals.len()))));
+        tiles
+    }
+
+    fn paint_portal(&mut self, pos: CellPos) {
+        match self.pending_portal.take() {
+            Some(first_end) if first_end != pos => {
+                self.level.clear_tile(first_end);
+                self.level.clear_tile(pos);
+                self.level.portals.push(PortalPair { a: first_end, b: pos });
+            },
+            Some(_) => {},
+            None if self.level.portals.len() >= MAX_PORTAL_PAIRS => {
+                self.status = EditorStatus::NoPortalLetters;
+            },
+            None => self.pending_portal = Some(pos)
+        }
+    }
+
+    /// Walls off everything outside a centered `size` by `size` square with rocks
+    fn set_playable_size(&mut self, size: usize) {
+        let old_border = get_border_width(self.playable_size);
+        let new_border = get_border_width(size);
+        for x in 0..GRID_SIZE {
+            for y in 0..GRID_SIZE {
+                let pos = CellPos { x, y };
+                let ring = get_ring(pos);
+                if ring < new_border {
+                    self.level.clear_tile(pos);
+                    self.level.rocks.push(pos);
+                } else if ring < old_border {
+                    self.level.clear_tile(pos);
+                }
+            }
+        }
+        self.playable_size = size;
+    }
+
+    fn is_playable(&self, pos: CellPos) -> bool { get_ring(pos) >= get_border_width(self.playable_size) }
+}
+
+/// The largest centered square whose surroundings are all rock
+fn get_playable_size(level: &Level) -> usize {
+    let mut size = GRID_SIZE;
+    while size > MIN_PLAYABLE_SIZE {
+        let ring = get_border_width(size);
+        let is_walled_off = (0..GRID_SIZE)
+            .flat_map(| x | (0..GRID_SIZE).map(move | y | CellPos { x, y }))
+            .filter(| &pos | get_ring(pos) == ring)
+            .all(| pos | level.rocks.contains(&pos));
+        if !is_walled_off {
+            break;
+        }
+        size -= 2;
+    }
+    size
+}
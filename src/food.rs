@@ -0,0 +1,91 @@
+use bevy::prelude::Color;
+use serde::{Serialize, Deserialize};
+
+use crate::{Sze, cell::CellPos};
+
+pub const MAX_FOOD_COUNT: usize = 10;
+
+pub const FOOD_KINDS: [FoodKind; 6] = [
+    FoodKind::Normal,
+    FoodKind::Golden,
+    FoodKind::Shrink,
+    FoodKind::Speed,
+    FoodKind::Slow,
+    FoodKind::Bonus
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FoodKind { Normal, Golden, Shrink, Speed, Slow, Bonus }
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct FoodItem {
+    pub pos: CellPos,
+    pub kind: FoodKind
+}
+
+/// How a single kind of food spawns, expires and scores
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FoodRule {
+    /// Relative chance of being picked whenever the regular food is replaced
+    pub spawn_weight: Sze,
+    /// Ticks the food stays on the board before despawning, `None` means forever
+    pub lifetime: Option<Sze>,
+    /// Points awarded before the score multiplier is applied
+    pub score: Sze,
+    /// Segments added to (or removed from, if negative) the snake
+    pub length_change: i32
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FoodConfig {
+    pub rules: [FoodRule; FOOD_KINDS.len()],
+    /// Regular (non bonus) food kept on the board at the same time, from 1 to `MAX_FOOD_COUNT`
+    pub food_count: usize,
+    /// Every tick there is a 1 in `bonus_spawn_one_in` chance of a bonus food showing up
+    pub bonus_spawn_one_in: u32
+}
+
+impl FoodKind {
+    pub fn get_color(&self) -> Color {
+        match self {
+            Self::Normal => Color::BLUE,
+            Self::Golden => Color::GOLD,
+            Self::Shrink => Color::PURPLE,
+            Self::Speed => Color::RED,
+            Self::Slow => Color::CYAN,
+            Self::Bonus => Color::PINK
+        }
+    }
+
+    pub fn get_message_id(&self) -> &'static str {
+        match self {
+            Self::Normal => "food-normal",
+            Self::Golden => "food-golden",
+            Self::Shrink => "food-shrink",
+            Self::Speed => "food-speed",
+            Self::Slow => "food-slow",
+            Self::Bonus => "food-bonus"
+        }
+    }
+}
+
+impl FoodConfig {
+    pub fn get_rule(&self, kind: FoodKind) -> &FoodRule { &self.rules[kind as usize] }
+}
+
+impl Default for FoodConfig {
+    fn default() -> Self {
+        FoodConfig {
+            rules: [
+                FoodRule { spawn_weight: 70, lifetime: None, score: 100, length_change: 1 },
+                FoodRule { spawn_weight: 8, lifetime: Some(40), score: 500, length_change: 1 },
+                FoodRule { spawn_weight: 8, lifetime: None, score: 50, length_change: -3 },
+                FoodRule { spawn_weight: 7, lifetime: None, score: 150, length_change: 1 },
+                FoodRule { spawn_weight: 7, lifetime: None, score: 50, length_change: 1 },
+                FoodRule { spawn_weight: 0, lifetime: Some(25), score: 300, length_change: 1 },
+            ],
+            food_count: 1,
+            bonus_spawn_one_in: 60
+        }
+    }
+}
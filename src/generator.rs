@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    cell::CellPos,
+    grid::GRID_SIZE,
+    level::Level,
+    simulation::{START_SNAKE_LENGHT, get_random_head_pos},
+    utils::DIRECTIONS
+};
+
+pub const MAX_DIFFICULTY: u32 = 10;
+
+const SMOOTHING_PASSES: usize = 2;
+/// A free cell with at least this many rocks around it (out of 8) turns into rock
+const ROCK_BIRTH_NEIGHBORS: usize = 5;
+/// A rock with fewer than this many rocks around it (out of 8) crumbles
+const ROCK_SURVIVAL_NEIGHBORS: usize = 4;
+/// Cells in front of the head kept free, on top of the ones the body starts on
+const SAFE_CORRIDOR_AHEAD: usize = START_SNAKE_LENGHT;
+/// Layouts leaving less of the board free than this are thrown away and rolled again
+const MIN_FREE_CELLS: usize = GRID_SIZE * GRID_SIZE * 3 / 5;
+const MAX_ATTEMPTS: usize = 50;
+
+type RockMap = [[bool; GRID_SIZE]; GRID_SIZE];
+
+/// Builds a cave-like rock layout with a cellular automaton, the same `seed` and `difficulty`
+/// always give the same level
+///
+/// `difficulty` goes from 0 to `MAX_DIFFICULTY` and sets how much of the board starts out as rock.
+/// Every free cell of the result can be reached from every other one and the snake starts on a
+/// clear row with `SAFE_CORRIDOR_AHEAD` free cells in front of it.
+pub fn generate_level(seed: u64, difficulty: u32) -> Level {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let rock_chance = 0.3 + 0.02 * difficulty.min(MAX_DIFFICULTY) as f64;
+    for _ in 0..MAX_ATTEMPTS {
+        let start = get_random_head_pos(&mut rng);
+        let mut rocks = [[false; GRID_SIZE]; GRID_SIZE];
+        rocks.iter_mut().flatten().for_each(| rock | *rock = rng.gen_bool(rock_chance));
+        for _ in 0..SMOOTHING_PASSES {
+            rocks = smooth(&rocks);
+        }
+        clear_start_corridor(&mut rocks, start);
+        fill_unreachable_cells(&mut rocks, start);
+        let free_cells = rocks.iter().flatten().filter(| &&rock | !rock).count();
+        if free_cells >= MIN_FREE_CELLS {
+            return Level { rocks: get_rock_cells(&rocks), start: Some(start), ..Default::default() };
+        }
+    }
+    Level { start: Some(get_random_head_pos(&mut rng)), ..Default::default() }
+}
+
+fn smooth(rocks: &RockMap) -> RockMap {
+    let mut smoothed = *rocks;
+    for x in 0..GRID_SIZE {
+        for y in 0..GRID_SIZE {
+            let rock_neighbors = count_rock_neighbors(rocks, x, y);
+            smoothed[x][y] = if rocks[x][y] {
+                rock_neighbors >= ROCK_SURVIVAL_NEIGHBORS
+            } else {
+                rock_neighbors >= ROCK_BIRTH_NEIGHBORS
+            };
+        }
+    }
+    smoothed
+}
+
+/// Cells outside the board don't count, so the edges stay mostly open
+fn count_rock_neighbors(rocks: &RockMap, x: usize, y: usize) -> usize {
+    let neighbors_y = y.saturating_sub(1)..=(y + 1).min(GRID_SIZE - 1);
+    (x.saturating_sub(1)..=(x + 1).min(GRID_SIZE - 1))
+        .flat_map(| neighbor_x | neighbors_y.clone().map(move | neighbor_y | (neighbor_x, neighbor_y)))
+        .filter(| &(neighbor_x, neighbor_y) | (neighbor_x, neighbor_y) != (x, y) && rocks[neighbor_x][neighbor_y])
+        .count()
+}
+
+fn clear_start_corridor(rocks: &mut RockMap, start: CellPos) {
+    let tail_x = start.x + 1 - START_SNAKE_LENGHT;
+    let corridor_end_x = (start.x + SAFE_CORRIDOR_AHEAD).min(GRID_SIZE - 1);
+    (tail_x..=corridor_end_x).for_each(| x | rocks[x][start.y] = false);
+}
+
+/// Turns every free cell the snake can't reach from `start` into rock
+fn fill_unreachable_cells(rocks: &mut RockMap, start: CellPos) {
+    let mut reached = [[false; GRID_SIZE]; GRID_SIZE];
+    let mut queue = VecDeque::from([start]);
+    reached[start.x][start.y] = true;
+    while let Some(pos) = queue.pop_front() {
+        for next in DIRECTIONS.iter().filter_map(| &dir | pos.step(dir)) {
+            if !rocks[next.x][next.y] && !reached[next.x][next.y] {
+                reached[next.x][next.y] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    for (rock_column, reached_column) in rocks.iter_mut().zip(reached.iter()) {
+        for (rock, is_reached) in rock_column.iter_mut().zip(reached_column.iter()) {
+            *rock |= !is_reached;
+        }
+    }
+}
+
+fn get_rock_cells(rocks: &RockMap) -> Vec<CellPos> {
+    rocks
+        .iter()
+        .enumerate()
+        .flat_map(| (x, column) | {
+            column.iter().enumerate().filter(| (_, &rock) | rock).map(move | (y, _) | CellPos { x, y })
+        })
+        .collect()
+}
@@ -0,0 +1,181 @@
+use std::{fs, io, path::{Path, PathBuf}};
+use bevy::prelude::Resource;
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    Sze,
+    cell::CellPos,
+    input::DirectionQueue,
+    replay::{Replay, ReplayEvent},
+    simulation::{Sim, SimSnapshot, StepEvents},
+    difficulty::Difficulty
+};
+
+const GHOSTS_DIR: &str = "saves/ghosts";
+
+/// The best ranked game played on a seed and difficulty, written as RON to `GHOSTS_DIR`
+#[derive(Serialize, Deserialize)]
+pub struct BestRun {
+    pub score: Sze,
+    pub replay: Replay
+}
+
+/// The best run on the seed and difficulty of the current game, stepped along with it
+///
+/// The ghost plays in its own `Sim` so it never collides with the live snake. It follows the live
+/// game's tick, a rewind takes it back to the snapshot kept with the live game's, anything else
+/// that takes the live game back starts the ghost over so it catches up.
+#[derive(Resource, Default)]
+pub struct Ghost {
+    /// Seed and difficulty the ghost was looked up for, set even when there is no best run on them
+    key: Option<(u64, Difficulty)>,
+    /// Read once per key, a new best run replaces it
+    best_run: Option<BestRun>,
+    sim: Option<Sim>,
+    cursor: ReplayCursor,
+    /// Tick of the live game at the last sync
+    last_tick: Sze
+}
+
+/// Where the ghost was when the live game was at a step the rewind buffer keeps
+pub struct GhostSnapshot {
+    sim: SimSnapshot,
+    cursor: ReplayCursor
+}
+
+/// Where a `Sim` stepped along a replay is in the replay's events
+///
+/// Events are logged in tick order, so each step only looks at the ones it hasn't passed yet.
+#[derive(Clone, Copy, Default)]
+pub struct ReplayCursor {
+    next_event: usize
+}
+
+impl BestRun {
+    /// Normal runs keep the name they had before there were difficulties
+    fn get_path(seed: u64, difficulty: Difficulty) -> PathBuf {
+        let file_name = match difficulty {
+            Difficulty::Normal => format!("{}.ron", seed),
+            _ => format!("{}-{}.ron", seed, difficulty.get_label().to_lowercase())
+        };
+        PathBuf::from(GHOSTS_DIR).join(file_name)
+    }
+
+    pub fn load(seed: u64, difficulty: Difficulty) -> io::Result<Self> { Self::load_from(&Self::get_path(seed, difficulty)) }
+
+    pub fn load_from(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents).map_err(| error | io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let contents = ron::ser::to_string(self).map_err(| error | io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::create_dir_all(GHOSTS_DIR)?;
+        fs::write(Self::get_path(self.replay.seed, self.replay.rules.difficulty), contents)
+    }
+}
+
+impl Ghost {
+    /// Brings the ghost to the same tick as `sim`
+    pub fn sync(&mut self, sim: &Sim) {
+        let seed = sim.get_replay().seed;
+        let key = (seed, sim.get_difficulty());
+        if self.key != Some(key) {
+            self.key = Some(key);
+            self.best_run = BestRun::load(seed, sim.get_difficulty()).ok();
+            self.start_over();
+        }
+        // Without a ghost yet, a restart still brings in a best run recorded since
+        let is_ahead = match &self.sim {
+            Some(ghost_sim) => ghost_sim.get_tick() > sim.get_tick(),
+            None => sim.get_tick() < self.last_tick
+        };
+        if is_ahead {
+            self.start_over();
+        }
+        self.last_tick = sim.get_tick();
+        if let (Some(ghost_sim), Some(best_run)) = (&mut self.sim, &self.best_run) {
+            while ghost_sim.is_game_running() && ghost_sim.get_tick() < sim.get_tick() {
+                self.cursor.step(ghost_sim, &best_run.replay);
+            }
+        }
+    }
+
+    pub fn take_snapshot(&mut self) -> Option<GhostSnapshot> {
+        let cursor = self.cursor;
+        self.sim.as_mut().map(| ghost_sim | GhostSnapshot { sim: ghost_sim.take_snapshot(), cursor })
+    }
+
+    /// Goes back to where the ghost was when `snapshot` was taken, if that's behind it in the same run
+    pub fn restore_snapshot(&mut self, snapshot: Option<GhostSnapshot>) {
+        if let (Some(ghost_sim), Some(snapshot)) = (&mut self.sim, snapshot) {
+            if snapshot.sim.precedes(ghost_sim) {
+                ghost_sim.restore_snapshot(snapshot.sim);
+                self.cursor = snapshot.cursor;
+            }
+        }
+    }
+
+    /// Keeps the replay of `sim` if it beat the best run on its seed and difficulty, rewound games don't count
+    pub fn record_finished_run(&mut self, sim: &Sim) {
+        let replay = sim.get_replay();
+        let key = (replay.seed, sim.get_difficulty());
+        let best_score = if self.key == Some(key) {
+            self.best_run.as_ref().map(| best_run | best_run.score)
+        } else {
+            BestRun::load(replay.seed, sim.get_difficulty()).map(| best_run | best_run.score).ok()
+        };
+        if !sim.is_score_ranked() || best_score.is_some_and(| best_score | best_score >= sim.get_score()) {
+            return;
+        }
+        let best_run = BestRun { score: sim.get_score(), replay: replay.clone() };
+        if let Err(error) = best_run.save() {
+            eprintln!("Couldn't save the best run for seed {}: {}", replay.seed, error);
+        }
+        if self.key == Some(key) {
+            self.best_run = Some(best_run);
+        }
+    }
+
+    /// How far ahead of the ghost `sim` is, `None` without a ghost
+    pub fn get_split(&self, sim: &Sim) -> Option<i64> {
+        self.sim.as_ref().map(| ghost_sim | sim.get_score() as i64 - ghost_sim.get_score() as i64)
+    }
+
+    pub fn get_snake_cells(&self) -> Vec<CellPos> {
+        self.sim.iter().flat_map(| ghost_sim | ghost_sim.get_snake_cells()).collect()
+    }
+
+    /// Back to the first tick of the best run, no ghost without one
+    fn start_over(&mut self) {
+        self.sim = self.best_run.as_ref().map(| best_run | {
+            let mut ghost_sim = Sim::new_simulation();
+            ghost_sim.set_rules(best_run.replay.rules.clone());
+            ghost_sim.reset_with_seed(best_run.replay.seed);
+            ghost_sim
+        });
+        self.cursor = ReplayCursor::default();
+    }
+}
+
+impl ReplayCursor {
+    /// Applies the settings and boost changes the player made before this tick, then takes the recorded move
+    ///
+    /// `sim` has to be the one this cursor stepped so far, started with the replay's seed and rules.
+    pub fn step(&mut self, sim: &mut Sim, replay: &Replay) -> StepEvents {
+        let tick = sim.get_tick();
+        while let Some(entry) = replay.events.get(self.next_event).filter(| entry | entry.tick <= tick) {
+            match entry.event {
+                ReplayEvent::FoodCountChanged(food_count) if entry.tick == tick => sim.set_food_count(food_count),
+                ReplayEvent::BoostChanged(is_boosting) if entry.tick == tick => sim.set_boosting(is_boosting),
+                _ => {}
+            }
+            self.next_event += 1;
+        }
+        let mut input_direction = DirectionQueue::default();
+        if let Some(&direction) = replay.moves.get(tick as usize) {
+            input_direction.push(direction);
+        }
+        sim.step(&mut input_direction)
+    }
+}
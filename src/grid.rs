@@ -1,37 +1,74 @@
+use rand::Rng;
 use bevy::prelude::Component;
+use serde::{Serialize, Serializer, Deserialize, Deserializer, de::Error};
 
-use crate::cell::{Cell, CellPos, CellContent};
+use crate::{cell::{Cell, CellPos, CellContent}, utils::get_stable_hash};
 
 pub const GRID_SIZE: usize = 15;
 
-#[derive(Component)]
+/// Keeps every empty cell in `free_cells` so a random one can be picked in O(1)
+///
+/// `free_index` maps each empty cell to its index in `free_cells`, and is `None` for occupied cells
+#[derive(Component, Clone)]
 pub struct Grid {
-    arr: [[Option<CellContent>; GRID_SIZE]; GRID_SIZE]
+    arr: [[Option<CellContent>; GRID_SIZE]; GRID_SIZE],
+    free_cells: Vec<CellPos>,
+    free_index: [[Option<usize>; GRID_SIZE]; GRID_SIZE]
 }
 
 impl Grid {
-    pub fn new_empty_grid() -> Self { Grid { arr: [[None; GRID_SIZE]; GRID_SIZE] }}
+    pub fn new_empty_grid() -> Self {
+        let mut grid = Grid {
+            arr: [[None; GRID_SIZE]; GRID_SIZE],
+            free_cells: Vec::with_capacity(GRID_SIZE * GRID_SIZE),
+            free_index: [[None; GRID_SIZE]; GRID_SIZE]
+        };
+        grid.clear_grid();
+        grid
+    }
 
     pub fn get_cell_content(&self, pos: CellPos) -> Option<CellContent> { self.arr[pos.x][pos.y] }
 
+    /// Hash of the serialized grid, see `get_stable_hash`
+    pub fn get_state_hash(&self) -> u64 { get_stable_hash(ron::to_string(self).unwrap_or_default().as_bytes()) }
+
     pub fn is_cell_empty(&self, pos: CellPos) -> bool { self.arr[pos.x][pos.y].is_none() }
 
     pub fn set_cell(&mut self, cell: Cell) {
+        self.remove_free_cell(cell.position);
         self.arr[cell.position.x][cell.position.y] = Some(cell.content);
     }
 
     pub fn clear_cell(&mut self, pos: CellPos) {
+        if self.arr[pos.x][pos.y].is_some() {
+            self.free_index[pos.x][pos.y] = Some(self.free_cells.len());
+            self.free_cells.push(pos);
+        }
         self.arr[pos.x][pos.y] = None;
     }
 
     pub fn clear_grid(&mut self) {
+        self.free_cells.clear();
         for x in 0..GRID_SIZE {
             for y in 0..GRID_SIZE {
                 self.arr[x][y] = None;
+                self.free_index[x][y] = Some(self.free_cells.len());
+                self.free_cells.push(CellPos { x, y });
             }
         }
     }
 
+    pub fn get_empty_cell_count(&self) -> usize { self.free_cells.len() }
+
+    /// Uniformly picks one of the empty cells, `None` only when the board is full
+    pub fn get_random_empty_cell<R: Rng>(&self, rng: &mut R) -> Option<CellPos> {
+        if self.free_cells.is_empty() {
+            None
+        } else {
+            Some(self.free_cells[rng.gen_range(0..self.free_cells.len())])
+        }
+    }
+
     pub fn get_occupied_cells(&self) -> Vec<Cell> {
         let mut ret_cells = vec!();
         for x in 0..GRID_SIZE {
@@ -43,4 +80,50 @@ impl Grid {
         }
         ret_cells
     }
+
+    fn remove_free_cell(&mut self, pos: CellPos) {
+        if let Some(index) = self.free_index[pos.x][pos.y].take() {
+            self.free_cells.swap_remove(index);
+            if let Some(&moved_pos) = self.free_cells.get(index) {
+                self.free_index[moved_pos.x][moved_pos.y] = Some(index);
+            }
+        }
+    }
+}
+
+/// The cells and the free cell list are written out, the free list keeps its order so random
+/// picks after loading are the same as they would have been without saving
+#[derive(Serialize)]
+struct GridRef<'a> {
+    arr: &'a [[Option<CellContent>; GRID_SIZE]; GRID_SIZE],
+    free_cells: &'a [CellPos]
+}
+
+#[derive(Deserialize)]
+struct GridData {
+    arr: [[Option<CellContent>; GRID_SIZE]; GRID_SIZE],
+    free_cells: Vec<CellPos>
+}
+
+impl Serialize for Grid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GridRef { arr: &self.arr, free_cells: &self.free_cells }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let GridData { arr, free_cells } = GridData::deserialize(deserializer)?;
+        let mut free_index = [[None; GRID_SIZE]; GRID_SIZE];
+        for (index, pos) in free_cells.iter().enumerate() {
+            if arr[pos.x][pos.y].is_some() || free_index[pos.x][pos.y].replace(index).is_some() {
+                return Err(D::Error::custom(format!("cell ({}, {}) is wrongly listed as free", pos.x, pos.y)));
+            }
+        }
+        let empty_cell_count = arr.iter().flatten().filter(| content | content.is_none()).count();
+        if empty_cell_count != free_cells.len() {
+            return Err(D::Error::custom("some empty cells are not listed as free"));
+        }
+        Ok(Grid { arr, free_cells, free_index })
+    }
 }
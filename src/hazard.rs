@@ -0,0 +1,181 @@
+use rand::{Rng, seq::SliceRandom};
+use bevy::prelude::Color;
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    Sze,
+    grid::Grid,
+    cell::{Cell, CellPos, CellContent},
+    utils::DIRECTIONS
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum HazardKind { Block, Wall, Lava, Rock }
+
+/// Obstacles on the board, all but rocks change every step
+///
+/// None of them ever overwrite a snake segment or food: blocks wait in place, walls stay open
+/// and lava only spreads into empty cells. Only the head can die from running into a hazard,
+/// or from being crushed by a moving block.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Hazard {
+    /// Walks `path` in a loop, one cell per step
+    Patrol { path: Vec<CellPos>, step: usize },
+    /// Alternates between closed (a hazard cell) and open (an empty cell)
+    TimedWall { pos: CellPos, is_open: bool, ticks_in_state: Sze },
+    /// Grows into a random empty neighbor every `lava_spread_every` ticks
+    Lava { cells: Vec<CellPos>, ticks_until_spread: Sze },
+    /// Never moves, only found in levels
+    Rock { pos: CellPos }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HazardConfig {
+    pub random_patrols: usize,
+    pub random_walls: usize,
+    pub random_lava: usize,
+    pub patrol_length: usize,
+    pub wall_open_ticks: Sze,
+    pub wall_closed_ticks: Sze,
+    pub lava_spread_every: Sze,
+    pub lava_max_cells: usize
+}
+
+impl HazardKind {
+    pub fn get_color(&self) -> Color {
+        match self {
+            Self::Block => Color::DARK_GRAY,
+            Self::Wall => Color::MAROON,
+            Self::Lava => Color::CRIMSON,
+            Self::Rock => Color::SILVER
+        }
+    }
+}
+
+impl HazardConfig {
+    /// A few of every hazard scattered over the board
+    pub fn with_random_hazards() -> Self {
+        HazardConfig { random_patrols: 2, random_walls: 3, random_lava: 1, ..Default::default() }
+    }
+}
+
+impl Hazard {
+    /// Places the hazard on the grid, all of its cells must be empty
+    pub fn place(&self, grid: &mut Grid) {
+        match self {
+            Self::Patrol { path, step } => set_hazard_cell(grid, path[*step], HazardKind::Block),
+            Self::TimedWall { pos, is_open, .. } => {
+                if !is_open { set_hazard_cell(grid, *pos, HazardKind::Wall) }
+            },
+            Self::Lava { cells, .. } => cells.iter().for_each(| &pos | set_hazard_cell(grid, pos, HazardKind::Lava)),
+            Self::Rock { pos } => set_hazard_cell(grid, *pos, HazardKind::Rock)
+        }
+    }
+
+    pub fn get_cells(&self) -> Vec<CellPos> {
+        match self {
+            Self::Patrol { path, step } => vec!(path[*step]),
+            Self::TimedWall { pos, .. } => vec!(*pos),
+            Self::Lava { cells, .. } => cells.clone(),
+            Self::Rock { pos } => vec!(*pos)
+        }
+    }
+
+    /// Every cell the hazard can take up, an open wall's and the rest of a patrol's path too
+    pub fn get_reserved_cells(&self) -> Vec<CellPos> {
+        match self {
+            Self::Patrol { path, .. } => path.clone(),
+            _ => self.get_cells()
+        }
+    }
+
+    /// Returns true if the hazard tried to move onto the snake head
+    pub fn step<R: Rng>(&mut self, grid: &mut Grid, head_pos: CellPos, config: &HazardConfig, rng: &mut R) -> bool {
+        match self {
+            Self::Patrol { path, step } => {
+                let cur_pos = path[*step];
+                let next_step = (*step + 1) % path.len();
+                let next_pos = path[next_step];
+                if next_pos == head_pos {
+                    return true;
+                }
+                if next_pos != cur_pos && grid.is_cell_empty(next_pos) {
+                    grid.clear_cell(cur_pos);
+                    set_hazard_cell(grid, next_pos, HazardKind::Block);
+                    *step = next_step;
+                }
+            },
+            Self::TimedWall { pos, is_open, ticks_in_state } => {
+                *ticks_in_state += 1;
+                if *is_open && *ticks_in_state >= config.wall_open_ticks && grid.is_cell_empty(*pos) {
+                    set_hazard_cell(grid, *pos, HazardKind::Wall);
+                    *is_open = false;
+                    *ticks_in_state = 0;
+                } else if !*is_open && *ticks_in_state >= config.wall_closed_ticks {
+                    grid.clear_cell(*pos);
+                    *is_open = true;
+                    *ticks_in_state = 0;
+                }
+            },
+            Self::Lava { cells, ticks_until_spread } => {
+                *ticks_until_spread = ticks_until_spread.saturating_sub(1);
+                if *ticks_until_spread > 0 || cells.len() >= config.lava_max_cells {
+                    return false;
+                }
+                *ticks_until_spread = config.lava_spread_every;
+                let candidates: Vec<CellPos> = cells
+                    .iter()
+                    .flat_map(| &pos | DIRECTIONS.iter().filter_map(move | &dir | pos.step(dir)))
+                    .filter(| &pos | grid.is_cell_empty(pos))
+                    .collect();
+                if let Some(&new_pos) = candidates.choose(rng) {
+                    set_hazard_cell(grid, new_pos, HazardKind::Lava);
+                    cells.push(new_pos);
+                }
+            },
+            Self::Rock { .. } => {}
+        }
+        false
+    }
+}
+
+/// Expands waypoints into the cell by cell loop a patrol walks, going back to the first one at the end
+///
+/// Consecutive waypoints must share a row or a column.
+pub fn get_patrol_path(waypoints: &[CellPos]) -> Vec<CellPos> {
+    let mut path = vec!();
+    for (index, &from) in waypoints.iter().enumerate() {
+        let to = waypoints[(index + 1) % waypoints.len()];
+        let mut pos = from;
+        while pos != to {
+            path.push(pos);
+            pos = CellPos {
+                x: (pos.x as i64 + (to.x as i64 - pos.x as i64).signum()) as usize,
+                y: (pos.y as i64 + (to.y as i64 - pos.y as i64).signum()) as usize
+            };
+        }
+    }
+    if path.is_empty() {
+        path.extend(waypoints.first());
+    }
+    path
+}
+
+fn set_hazard_cell(grid: &mut Grid, pos: CellPos, kind: HazardKind) {
+    grid.set_cell(Cell { position: pos, content: CellContent::Hazard { kind } });
+}
+
+impl Default for HazardConfig {
+    fn default() -> Self {
+        HazardConfig {
+            random_patrols: 0,
+            random_walls: 0,
+            random_lava: 0,
+            patrol_length: 5,
+            wall_open_ticks: 20,
+            wall_closed_ticks: 15,
+            lava_spread_every: 25,
+            lava_max_cells: 12
+        }
+    }
+}
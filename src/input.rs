@@ -1,12 +1,30 @@
 use std::collections::HashMap;
 use bevy::prelude::{Resource, KeyCode, ResMut, Res, Input};
+use serde::{Serialize, Deserialize};
 
-use crate::utils::Direction;
+use crate::{utils::Direction, locale::Locale};
 
 pub const BOOST_GAME_KEY: CommandKey = CommandKey { keycode: KeyCode::Space, str: "Space" };
 pub const START_GAME_KEY: CommandKey = CommandKey { keycode: KeyCode::M, str: "M" };
 pub const RESTART_GAME_KEY: CommandKey = CommandKey { keycode: KeyCode::R, str: "R" };
 pub const PAUSE_GAME_KEY: CommandKey = CommandKey { keycode: KeyCode::P, str: "P" };
+pub const DAILY_GAME_KEY: CommandKey = CommandKey { keycode: KeyCode::D, str: "D" };
+pub const DAILY_PRACTICE_KEY: CommandKey = CommandKey { keycode: KeyCode::T, str: "T" };
+pub const REWIND_KEY: CommandKey = CommandKey { keycode: KeyCode::Z, str: "Z" };
+pub const CONTINUE_GAME_KEY: CommandKey = CommandKey { keycode: KeyCode::C, str: "C" };
+pub const STATS_KEY: CommandKey = CommandKey { keycode: KeyCode::S, str: "S" };
+pub const ACHIEVEMENTS_KEY: CommandKey = CommandKey { keycode: KeyCode::A, str: "A" };
+pub const LANGUAGE_KEY: CommandKey = CommandKey { keycode: KeyCode::L, str: "L" };
+pub const EDITOR_KEY: CommandKey = CommandKey { keycode: KeyCode::E, str: "E" };
+pub const EDITOR_SAVE_KEY: CommandKey = CommandKey { keycode: KeyCode::S, str: "S" };
+pub const EDITOR_LOAD_KEY: CommandKey = CommandKey { keycode: KeyCode::L, str: "L" };
+pub const EDITOR_GROW_KEY: CommandKey = CommandKey { keycode: KeyCode::BracketRight, str: "]" };
+pub const EDITOR_SHRINK_KEY: CommandKey = CommandKey { keycode: KeyCode::BracketLeft, str: "[" };
+/// Picks the matching entry of `EDITOR_TOOLS`
+pub const EDITOR_TOOL_KEYS: [KeyCode; 6] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5, KeyCode::Key6];
+pub const MENU_UP_KEY: CommandKey = CommandKey { keycode: KeyCode::Up, str: "Up" };
+pub const MENU_DOWN_KEY: CommandKey = CommandKey { keycode: KeyCode::Down, str: "Down" };
+pub const MENU_SELECT_KEY: CommandKey = CommandKey { keycode: KeyCode::Return, str: "Enter" };
 
 pub struct CommandKey {
     pub keycode: KeyCode,
@@ -19,7 +37,7 @@ pub struct PlayerInput {
     pub is_boost_active: bool,
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct DirectionQueue {
     next: Option<Direction>,
     queued_1: Option<Direction>,
@@ -63,6 +81,14 @@ impl DirectionQueue {
     }
 }
 
+pub fn get_controls_description(locale: &Locale) -> String {
+    locale.format("controls", &[
+        ("boost", BOOST_GAME_KEY.str.into()),
+        ("pause", PAUSE_GAME_KEY.str.into()),
+        ("rewind", REWIND_KEY.str.into())
+    ])
+}
+
 pub fn handle_player_input(mut input: ResMut<PlayerInput>, keyboard_input: Res<Input<KeyCode>>) {
     let dir_map = HashMap::from([
         (KeyCode::Up, Direction::Up),
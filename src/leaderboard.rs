@@ -0,0 +1,759 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    path::Path,
+    sync::{mpsc, Mutex},
+    thread,
+    time::Duration
+};
+use bevy::prelude::Resource;
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+
+use crate::{
+    Sze,
+    ghost::ReplayCursor,
+    replay::Replay,
+    rules::{GameRules, TimeAttackRules},
+    simulation::Sim,
+    difficulty::{Difficulty, DIFFICULTIES},
+    food::{FoodConfig, MAX_FOOD_COUNT},
+    powerup::PowerUpConfig,
+    hazard::HazardConfig,
+    level::Level,
+    settings::MAX_RANDOM_PORTAL_PAIRS,
+    daily::{DailyDate, get_daily_rules},
+    timers::StaminaCheck,
+    locale::Locale
+};
+
+pub const DEFAULT_LEADERBOARD_PORT: u16 = 9002;
+pub const DEFAULT_LEADERBOARD_PATH: &str = "saves/leaderboard.ron";
+/// Entries per page when a query doesn't ask for a number
+pub const DEFAULT_PAGE_SIZE: usize = 10;
+const MAX_PAGE_SIZE: usize = 100;
+/// Caps a whole request, a day long replay fits easily
+const MAX_REQUEST_BYTES: u64 = 4 * 1024 * 1024;
+/// Longer replays are turned down before simulating them, a day at four ticks a second
+const MAX_REPLAY_TICKS: Sze = 24 * 60 * 60 * 4;
+/// Replays logging more events are turned down too, even toggling boost every tick stays below
+const MAX_REPLAY_EVENTS: usize = 2 * MAX_REPLAY_TICKS as usize;
+const MAX_PLAYER_NAME_CHARS: usize = 16;
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const GAME_MODES: [GameMode; 3] = [GameMode::Classic, GameMode::TimeAttack, GameMode::Level];
+
+/// Which board a game goes on, told by the rules it started with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum GameMode { Classic, TimeAttack, Level }
+
+/// One board, games on levels are ranked separately for every level
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BoardId {
+    pub mode: GameMode,
+    pub difficulty: Difficulty,
+    /// `Level::get_hash` of the level, only level boards have one
+    pub level_hash: Option<u64>
+}
+
+/// A finished game sent to the server, the score only counts if the replay reaches it
+#[derive(Serialize, Deserialize)]
+pub struct Submission {
+    pub player: String,
+    pub mode: GameMode,
+    pub score: Sze,
+    pub seed: u64,
+    pub replay: Replay
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub player: String,
+    pub score: Sze,
+    pub seed: u64,
+    /// How long the game lasted
+    pub ticks: Sze
+}
+
+/// Where the player's best game on a board stands after a submission, `rank` starts at 1
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubmissionReceipt {
+    pub board: BoardId,
+    pub rank: usize,
+    pub total: usize,
+    pub is_new_best: bool
+}
+
+/// Entries of a board from `offset` on, `total` counts all of them
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LeaderboardPage {
+    pub offset: usize,
+    pub entries: Vec<LeaderboardEntry>,
+    pub total: usize
+}
+
+/// The best game of every player on every board, highest score first
+///
+/// Ties keep the game that was submitted first ahead.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Leaderboard {
+    boards: BTreeMap<BoardId, Vec<LeaderboardEntry>>
+}
+
+/// Why the server turned a submission down, sent as RON so the client can tell it in its language
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RejectReason {
+    Unreadable,
+    NoPlayerName,
+    SeedMismatch,
+    /// The mode the submission claims
+    WrongMode(GameMode),
+    TooLong,
+    EventsOutOfOrder,
+    BrokenLevel,
+    UnrankedRules,
+    BoostsPastStamina { tick: Sze },
+    UnfinishedGame,
+    WrongScore { replayed: Sze, submitted: Sze }
+}
+
+/// Why a submission didn't make it onto a board
+#[derive(Debug)]
+pub enum SubmitError {
+    Unsendable(String),
+    Unreachable { address: String, error: String },
+    /// The server's reason, like a replay that doesn't reach the score
+    TurnedDown(RejectReason),
+    BadAnswer { address: String, error: String }
+}
+
+/// Where to send finished games and who they are from, read from `--leaderboard` and `--player`
+#[derive(Clone)]
+pub struct LeaderboardConfig {
+    pub address: String,
+    pub player: String
+}
+
+/// Uploads finished games to a leaderboard server without holding up the game
+#[derive(Resource)]
+pub struct LeaderboardClient {
+    config: LeaderboardConfig,
+    sender: mpsc::Sender<Result<SubmissionReceipt, SubmitError>>,
+    /// What became of the uploads, only locked because a `Resource` has to be `Sync`
+    results: Mutex<mpsc::Receiver<Result<SubmissionReceipt, SubmitError>>>
+}
+
+struct Request {
+    method: String,
+    target: String,
+    body: Vec<u8>
+}
+
+struct Response {
+    status: u16,
+    body: String
+}
+
+impl GameMode {
+    /// Level games go on the level board even when they are timed
+    pub fn of(rules: &GameRules) -> Self {
+        if rules.level.is_some() {
+            Self::Level
+        } else if rules.time_attack.is_some() {
+            Self::TimeAttack
+        } else {
+            Self::Classic
+        }
+    }
+
+    pub fn get_message_id(&self) -> &'static str {
+        match self {
+            Self::Classic => "mode-classic",
+            Self::TimeAttack => "mode-time-attack",
+            Self::Level => "mode-level"
+        }
+    }
+
+    /// How the mode is written in board paths
+    pub fn get_path_name(&self) -> &'static str {
+        match self {
+            Self::Classic => "classic",
+            Self::TimeAttack => "time-attack",
+            Self::Level => "level"
+        }
+    }
+}
+
+impl BoardId {
+    pub fn of(rules: &GameRules) -> Self {
+        BoardId { mode: GameMode::of(rules), difficulty: rules.difficulty, level_hash: rules.level.as_ref().map(Level::get_hash) }
+    }
+}
+
+/// Reads a board written as `<mode>/<difficulty>`, like `time-attack/hard`, level boards end
+/// with the level hash in hex, like `level/normal/3f2a9c0d17e4b6a5`
+pub fn parse_board_path(path: &str) -> Option<BoardId> {
+    let mut parts = path.split('/');
+    let (Some(mode), Some(difficulty), level_hash, None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return None;
+    };
+    let mode = *GAME_MODES.iter().find(| game_mode | game_mode.get_path_name() == mode)?;
+    let difficulty = *DIFFICULTIES.iter().find(| preset | preset.get_label().eq_ignore_ascii_case(difficulty))?;
+    let level_hash = match (mode, level_hash) {
+        (GameMode::Level, Some(level_hash)) => Some(u64::from_str_radix(level_hash, 16).ok()?),
+        (GameMode::Classic | GameMode::TimeAttack, None) => None,
+        _ => return None
+    };
+    Some(BoardId { mode, difficulty, level_hash })
+}
+
+pub fn get_board_path(board: BoardId) -> String {
+    let path = format!("{}/{}", board.mode.get_path_name(), board.difficulty.get_label().to_lowercase());
+    match board.level_hash {
+        Some(level_hash) => format!("{}/{:016x}", path, level_hash),
+        None => path
+    }
+}
+
+impl SubmissionReceipt {
+    pub fn get_description(&self, locale: &Locale) -> String {
+        locale.format(if self.is_new_best { "leaderboard-new-best" } else { "leaderboard-best-stays" }, &[
+            ("rank", self.rank.into()),
+            ("total", self.total.into()),
+            ("mode", locale.get(self.board.mode.get_message_id()).into()),
+            ("difficulty", locale.get(self.board.difficulty.get_message_id()).into())
+        ])
+    }
+}
+
+impl SubmitError {
+    pub fn get_description(&self, locale: &Locale) -> String {
+        match self {
+            Self::Unsendable(error) => locale.format("leaderboard-unsendable", &[("error", error.as_str().into())]),
+            Self::Unreachable { address, error } => locale.format("leaderboard-unreachable", &[
+                ("address", address.as_str().into()),
+                ("error", error.as_str().into())
+            ]),
+            Self::TurnedDown(reason) => locale.format("leaderboard-turned-down", &[("reason", reason.get_description(locale).into())]),
+            Self::BadAnswer { address, error } => locale.format("leaderboard-bad-answer", &[
+                ("address", address.as_str().into()),
+                ("error", error.as_str().into())
+            ])
+        }
+    }
+}
+
+impl Leaderboard {
+    /// Starts empty if there is no file yet, an unreadable one is an error so it doesn't get overwritten
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => ron::from_str(&contents).map_err(| error | io::Error::new(io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Leaderboard::default()),
+            Err(error) => Err(error)
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let contents = ron::ser::to_string(self).map_err(| error | io::Error::new(io::ErrorKind::InvalidData, error))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, contents)
+    }
+
+    /// Keeps `entry` if it beats the player's best on the board
+    pub fn add(&mut self, board_id: BoardId, entry: LeaderboardEntry) -> SubmissionReceipt {
+        let board = self.boards.entry(board_id).or_default();
+        let best_index = board.iter().position(| best | best.player == entry.player);
+        let is_new_best = best_index.map_or(true, | index | board[index].score < entry.score);
+        let player = entry.player.clone();
+        if is_new_best {
+            if let Some(index) = best_index {
+                board.remove(index);
+            }
+            let index = board.partition_point(| other | other.score >= entry.score);
+            board.insert(index, entry);
+        }
+        let rank = board.iter().position(| best | best.player == player).map_or(0, | index | index + 1);
+        SubmissionReceipt { board: board_id, rank, total: board.len(), is_new_best }
+    }
+
+    pub fn get_page(&self, board_id: BoardId, offset: usize, limit: usize) -> LeaderboardPage {
+        let board = self.boards.get(&board_id).map_or(&[][..], | board | board.as_slice());
+        LeaderboardPage {
+            offset,
+            entries: board.iter().skip(offset).take(limit).cloned().collect(),
+            total: board.len()
+        }
+    }
+}
+
+impl RejectReason {
+    pub fn get_description(&self, locale: &Locale) -> String {
+        match self {
+            Self::Unreadable => locale.get("reject-unreadable"),
+            Self::NoPlayerName => locale.get("reject-no-player-name"),
+            Self::SeedMismatch => locale.get("reject-seed-mismatch"),
+            Self::WrongMode(mode) => locale.format("reject-wrong-mode", &[("mode", locale.get(mode.get_message_id()).into())]),
+            Self::TooLong => locale.get("reject-too-long"),
+            Self::EventsOutOfOrder => locale.get("reject-events-out-of-order"),
+            Self::BrokenLevel => locale.get("reject-broken-level"),
+            Self::UnrankedRules => locale.get("reject-unranked-rules"),
+            Self::BoostsPastStamina { tick } => locale.format("reject-boosts-past-stamina", &[("tick", (*tick).into())]),
+            Self::UnfinishedGame => locale.get("reject-unfinished-game"),
+            Self::WrongScore { replayed, submitted } => locale.format("reject-wrong-score", &[
+                ("replayed", (*replayed).into()),
+                ("submitted", (*submitted).into())
+            ])
+        }
+    }
+}
+
+/// Plays the replay again and checks that it ends with the submitted score, returns the tick it ended on
+pub fn verify_submission(submission: &Submission) -> Result<Sze, RejectReason> {
+    let replay = &submission.replay;
+    if submission.seed != replay.seed {
+        return Err(RejectReason::SeedMismatch);
+    }
+    if submission.mode != GameMode::of(&replay.rules) {
+        return Err(RejectReason::WrongMode(submission.mode));
+    }
+    if replay.moves.len() > MAX_REPLAY_TICKS as usize || replay.events.len() > MAX_REPLAY_EVENTS {
+        return Err(RejectReason::TooLong);
+    }
+    if replay.events.windows(2).any(| entries | entries[0].tick > entries[1].tick) {
+        return Err(RejectReason::EventsOutOfOrder);
+    }
+    if replay.rules.level.as_ref().is_some_and(| level | level.validate().is_err()) {
+        return Err(RejectReason::BrokenLevel);
+    }
+    if !has_ranked_rules(replay) {
+        return Err(RejectReason::UnrankedRules);
+    }
+    let mut sim = Sim::new_simulation();
+    sim.set_rules(replay.rules.clone());
+    sim.reset_with_seed(replay.seed);
+    let mut cursor = ReplayCursor::default();
+    let mut stamina = StaminaCheck::new(replay.rules.difficulty);
+    // A time out ends the game on the tick after the last recorded move
+    while sim.is_game_running() && sim.get_tick() as usize <= replay.moves.len() {
+        let events = cursor.step(&mut sim, replay);
+        if !stamina.on_tick(sim.is_boosting()) {
+            return Err(RejectReason::BoostsPastStamina { tick: sim.get_tick() });
+        }
+        if let Some(food_eaten) = &events.food_eaten {
+            stamina.on_food_eaten(food_eaten);
+        }
+    }
+    // Running straight past the end of a cut short replay can end the game too
+    if sim.is_game_running() || sim.get_replay().moves.len() != replay.moves.len() {
+        return Err(RejectReason::UnfinishedGame);
+    }
+    if sim.get_score() != submission.score {
+        return Err(RejectReason::WrongScore { replayed: sim.get_score(), submitted: submission.score });
+    }
+    Ok(sim.get_tick())
+}
+
+/// Ranked games start with the rules the settings menu gives, or with the daily challenge of their seed
+fn has_ranked_rules(replay: &Replay) -> bool {
+    let is_daily = DailyDate::from_seed(replay.seed).is_some_and(| date | get_daily_rules(date) == replay.rules);
+    is_daily || get_standard_rules(&replay.rules) == replay.rules
+}
+
+/// The rules the settings menu gives a game, with the choices it offers taken from `rules`
+///
+/// Those are the difficulty, the food count, the random portal pairs, whether there are random
+/// hazards and the clock, plus the level it was played on. Choices the menu can't make are
+/// brought back into its range, so they don't match `rules` anymore.
+fn get_standard_rules(rules: &GameRules) -> GameRules {
+    let hazard_config = if rules.hazard_config == HazardConfig::default() {
+        HazardConfig::default()
+    } else {
+        HazardConfig::with_random_hazards()
+    };
+    GameRules {
+        food_config: FoodConfig { food_count: rules.food_config.food_count.clamp(1, MAX_FOOD_COUNT), ..FoodConfig::default() },
+        power_up_config: PowerUpConfig::default(),
+        hazard_config,
+        random_portal_pairs: rules.random_portal_pairs.min(MAX_RANDOM_PORTAL_PAIRS),
+        level: rules.level.clone(),
+        time_attack: rules.time_attack.as_ref().map(| _ | TimeAttackRules::default()),
+        difficulty: rules.difficulty
+    }
+}
+
+/// Trims the name and drops what doesn't print, `None` if nothing is left
+fn get_player_name(name: &str) -> Option<String> {
+    let name: String = name.trim().chars().filter(| c | !c.is_control()).take(MAX_PLAYER_NAME_CHARS).collect();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Answers leaderboard requests on `port` with the boards kept in `path`, only returns if it can't start
+///
+/// `POST /scores` takes a RON `Submission` and answers with a `SubmissionReceipt`, or 400 or 422
+/// and the `RejectReason` it was turned down for. `GET /scores/<board>?offset=<n>&limit=<n>` answers with a
+/// `LeaderboardPage`, see `parse_board_path` for how boards are written.
+pub fn serve(port: u16, path: &Path) -> io::Result<()> {
+    let leaderboard = Leaderboard::load(path)?;
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    let leaderboard = &Mutex::new(leaderboard);
+    thread::scope(| scope | {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    scope.spawn(move || {
+                        if let Err(error) = handle_connection(stream, leaderboard, path) {
+                            eprintln!("Dropped a leaderboard request: {}", error);
+                        }
+                    });
+                },
+                Err(error) => eprintln!("Couldn't accept a leaderboard connection: {}", error)
+            }
+        }
+    });
+    Ok(())
+}
+
+fn handle_connection(stream: TcpStream, leaderboard: &Mutex<Leaderboard>, path: &Path) -> io::Result<()> {
+    stream.set_read_timeout(Some(NETWORK_TIMEOUT))?;
+    stream.set_write_timeout(Some(NETWORK_TIMEOUT))?;
+    let response = match read_request(&stream) {
+        Ok(request) => route(&request, leaderboard, path),
+        Err(error) => Response { status: 400, body: error.to_string() }
+    };
+    write_response(&stream, &response)
+}
+
+fn read_request(stream: &TcpStream) -> io::Result<Request> {
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "malformed request line"));
+    };
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the headers never end"));
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()
+                    .ok()
+                    .filter(| length | *length <= MAX_REQUEST_BYTES)
+                    .ok_or_else(| | io::Error::new(io::ErrorKind::InvalidData, "bad content length"))?;
+            }
+        }
+    }
+    let mut body = vec!(0; content_length as usize);
+    reader.read_exact(&mut body)?;
+    Ok(Request { method: method.to_string(), target: target.to_string(), body })
+}
+
+fn write_response(mut stream: &TcpStream, response: &Response) -> io::Result<()> {
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        422 => "Unprocessable Entity",
+        _ => "Internal Server Error"
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status, reason, response.body.len(), response.body
+    )?;
+    stream.flush()
+}
+
+fn route(request: &Request, leaderboard: &Mutex<Leaderboard>, path: &Path) -> Response {
+    let (request_path, query) = request.target.split_once('?').unwrap_or((&request.target, ""));
+    match (request.method.as_str(), request_path.strip_prefix("/scores")) {
+        ("POST", Some("" | "/")) => submit_entry(&request.body, leaderboard, path),
+        ("GET", Some(board)) => match parse_board_path(board.trim_matches('/')) {
+            Some(board) => get_board_page(board, query, leaderboard),
+            None => Response { status: 404, body: "no such board".to_string() }
+        },
+        _ => Response { status: 404, body: "not found".to_string() }
+    }
+}
+
+fn submit_entry(body: &[u8], leaderboard: &Mutex<Leaderboard>, path: &Path) -> Response {
+    let submission = match std::str::from_utf8(body).map_err(| error | error.to_string())
+        .and_then(| body | ron::from_str::<Submission>(body).map_err(| error | error.to_string()))
+    {
+        Ok(submission) => submission,
+        Err(_) => return to_response(400, &RejectReason::Unreadable)
+    };
+    let Some(player) = get_player_name(&submission.player) else {
+        return to_response(400, &RejectReason::NoPlayerName);
+    };
+    let ticks = match verify_submission(&submission) {
+        Ok(ticks) => ticks,
+        Err(reason) => return to_response(422, &reason)
+    };
+    let entry = LeaderboardEntry { player, score: submission.score, seed: submission.seed, ticks };
+    // A request that panicked can't have left a board half changed, it only reads or adds one entry
+    let mut leaderboard = leaderboard.lock().unwrap_or_else(| error | error.into_inner());
+    let receipt = leaderboard.add(BoardId::of(&submission.replay.rules), entry);
+    if receipt.is_new_best {
+        if let Err(error) = leaderboard.save(path) {
+            eprintln!("Couldn't save the leaderboard to {}: {}", path.display(), error);
+        }
+    }
+    to_response(200, &receipt)
+}
+
+fn get_board_page(board: BoardId, query: &str, leaderboard: &Mutex<Leaderboard>) -> Response {
+    let mut offset = 0;
+    let mut limit = DEFAULT_PAGE_SIZE;
+    for (name, value) in query.split('&').filter_map(| parameter | parameter.split_once('=')) {
+        let target = match name {
+            "offset" => &mut offset,
+            "limit" => &mut limit,
+            _ => continue
+        };
+        match value.parse() {
+            Ok(value) => *target = value,
+            Err(_) => return Response { status: 400, body: format!("{} is not a number", name) }
+        }
+    }
+    let leaderboard = leaderboard.lock().unwrap_or_else(| error | error.into_inner());
+    to_response(200, &leaderboard.get_page(board, offset, limit.min(MAX_PAGE_SIZE)))
+}
+
+fn to_response(status: u16, value: &impl Serialize) -> Response {
+    match ron::to_string(value) {
+        Ok(body) => Response { status, body },
+        Err(error) => Response { status: 500, body: error.to_string() }
+    }
+}
+
+impl LeaderboardClient {
+    pub fn new(config: LeaderboardConfig) -> Self {
+        let (sender, results) = mpsc::channel();
+        LeaderboardClient { config, sender, results: Mutex::new(results) }
+    }
+
+    /// Uploads the game `sim` just finished, what became of it comes out of `pop_message` later
+    pub fn submit(&self, sim: &Sim) {
+        let replay = sim.get_replay().clone();
+        let submission = Submission {
+            player: self.config.player.clone(),
+            mode: GameMode::of(&replay.rules),
+            score: sim.get_score(),
+            seed: replay.seed,
+            replay
+        };
+        let address = self.config.address.clone();
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            // Only fails once the game closed
+            sender.send(submit(&address, &submission)).ok();
+        });
+    }
+
+    pub fn pop_message(&self, locale: &Locale) -> Option<String> {
+        let result = self.results.lock().ok()?.try_recv().ok()?;
+        Some(match result {
+            Ok(receipt) => receipt.get_description(locale),
+            Err(error) => error.get_description(locale)
+        })
+    }
+}
+
+/// Sends a finished game to the server at `address` and waits for the verdict
+pub fn submit(address: &str, submission: &Submission) -> Result<SubmissionReceipt, SubmitError> {
+    let body = ron::to_string(submission).map_err(| error | SubmitError::Unsendable(error.to_string()))?;
+    match send_request(address, "POST", "/scores", &body) {
+        Ok((200, body)) => ron::from_str(&body)
+            .map_err(| error | SubmitError::BadAnswer { address: address.to_string(), error: error.to_string() }),
+        Ok((_, body)) => match ron::from_str(&body) {
+            Ok(reason) => Err(SubmitError::TurnedDown(reason)),
+            Err(_) => Err(SubmitError::BadAnswer { address: address.to_string(), error: body })
+        },
+        Err(error) => Err(SubmitError::Unreachable { address: address.to_string(), error: error.to_string() })
+    }
+}
+
+pub fn get_page(address: &str, board: BoardId, offset: usize, limit: usize) -> io::Result<LeaderboardPage> {
+    let target = format!("/scores/{}?offset={}&limit={}", get_board_path(board), offset, limit);
+    match send_request(address, "GET", &target, "")? {
+        (200, body) => parse_ron(&body),
+        (_, reason) => Err(io::Error::new(io::ErrorKind::Other, reason))
+    }
+}
+
+fn parse_ron<T: DeserializeOwned>(body: &str) -> io::Result<T> {
+    ron::from_str(body).map_err(| error | io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Returns the status and body of the answer
+fn send_request(address: &str, method: &str, target: &str, body: &str) -> io::Result<(u16, String)> {
+    let socket_address = address.to_socket_addrs()?
+        .next()
+        .ok_or_else(| | io::Error::new(io::ErrorKind::NotFound, "the address doesn't resolve"))?;
+    let mut stream = TcpStream::connect_timeout(&socket_address, NETWORK_TIMEOUT)?;
+    stream.set_read_timeout(Some(NETWORK_TIMEOUT))?;
+    stream.set_write_timeout(Some(NETWORK_TIMEOUT))?;
+    write!(
+        stream,
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/ron\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        method, target, address, body.len(), body
+    )?;
+    stream.flush()?;
+    let mut response = String::new();
+    stream.take(MAX_REQUEST_BYTES).read_to_string(&mut response)?;
+    let malformed = | | io::Error::new(io::ErrorKind::InvalidData, "malformed answer");
+    let (head, body) = response.split_once("\r\n\r\n").ok_or_else(malformed)?;
+    let status = head.split_whitespace().nth(1).and_then(| status | status.parse().ok()).ok_or_else(malformed)?;
+    Ok((status, body.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{input::DirectionQueue, utils::{Direction, DIRECTIONS}};
+
+    /// Turns away from walls and the body until the snake runs out of room, boosting on the ticks `is_boosting` picks
+    fn play_game(seed: u64, is_boosting: impl Fn(Sze) -> bool) -> Submission {
+        let mut sim = Sim::new_simulation();
+        sim.set_rules(GameRules::default());
+        sim.reset_with_seed(seed);
+        let mut input_direction = DirectionQueue::default();
+        while sim.is_game_running() {
+            let head_pos = sim.get_head_position();
+            let snake_cells = sim.get_snake_cells();
+            let turn = (sim.get_tick() / 7) as usize;
+            let direction = (0..DIRECTIONS.len())
+                .map(| index | DIRECTIONS[(turn + index) % DIRECTIONS.len()])
+                .find(| &direction | head_pos.step(direction).is_some_and(| pos | !snake_cells.contains(&pos)))
+                .unwrap_or(Direction::Up);
+            input_direction.push(direction);
+            sim.set_boosting(is_boosting(sim.get_tick()));
+            sim.step(&mut input_direction);
+        }
+        let replay = sim.get_replay().clone();
+        Submission { player: "Alice".to_string(), mode: GameMode::of(&replay.rules), score: sim.get_score(), seed, replay }
+    }
+
+    fn play_honest_game(seed: u64) -> Submission { play_game(seed, | tick | tick % 10 < 3) }
+
+    fn entry(player: &str, score: Sze) -> LeaderboardEntry {
+        LeaderboardEntry { player: player.to_string(), score, seed: 0, ticks: 0 }
+    }
+
+    fn get_players(page: &LeaderboardPage) -> Vec<&str> {
+        page.entries.iter().map(| entry | entry.player.as_str()).collect()
+    }
+
+    const CLASSIC: BoardId = BoardId { mode: GameMode::Classic, difficulty: Difficulty::Normal, level_hash: None };
+
+    #[test]
+    fn honest_replays_are_accepted() {
+        for seed in 0..20 {
+            let submission = play_honest_game(seed);
+            assert_eq!(verify_submission(&submission), Ok(submission.replay.moves.len() as Sze));
+        }
+    }
+
+    #[test]
+    fn tampered_scores_are_turned_down() {
+        let mut submission = play_honest_game(1);
+        submission.score += 100;
+        assert_eq!(verify_submission(&submission), Err(RejectReason::WrongScore {
+            replayed: submission.score - 100,
+            submitted: submission.score
+        }));
+    }
+
+    #[test]
+    fn tampered_rules_are_turned_down() {
+        let mut submission = play_honest_game(2);
+        submission.replay.rules.food_config.rules[0].score *= 10;
+        assert_eq!(verify_submission(&submission), Err(RejectReason::UnrankedRules));
+
+        let mut submission = play_honest_game(2);
+        submission.replay.rules.time_attack = Some(TimeAttackRules { budget_ticks: 1000, bonus_ticks_per_food: 1000 });
+        submission.mode = GameMode::TimeAttack;
+        assert_eq!(verify_submission(&submission), Err(RejectReason::UnrankedRules));
+
+        let mut submission = play_honest_game(2);
+        submission.replay.rules.level = Some(Level { start: Some(crate::cell::CellPos { x: 0, y: 0 }), ..Level::default() });
+        submission.mode = GameMode::Level;
+        assert_eq!(verify_submission(&submission), Err(RejectReason::BrokenLevel));
+    }
+
+    #[test]
+    fn endless_boosts_are_turned_down() {
+        let submission = play_game(3, | _ | true);
+        assert!(submission.replay.moves.len() > 100);
+        assert!(matches!(verify_submission(&submission), Err(RejectReason::BoostsPastStamina { .. })));
+    }
+
+    #[test]
+    fn replays_with_events_out_of_order_are_turned_down() {
+        let mut submission = play_honest_game(4);
+        submission.replay.events.reverse();
+        assert_eq!(verify_submission(&submission), Err(RejectReason::EventsOutOfOrder));
+    }
+
+    #[test]
+    fn ties_keep_the_first_submission_ahead() {
+        let mut leaderboard = Leaderboard::default();
+        leaderboard.add(CLASSIC, entry("Alice", 300));
+        leaderboard.add(CLASSIC, entry("Bob", 500));
+        let receipt = leaderboard.add(CLASSIC, entry("Carol", 300));
+        assert_eq!((receipt.rank, receipt.total, receipt.is_new_best), (3, 3, true));
+        assert_eq!(get_players(&leaderboard.get_page(CLASSIC, 0, 10)), ["Bob", "Alice", "Carol"]);
+    }
+
+    #[test]
+    fn only_a_better_score_replaces_the_players_best() {
+        let mut leaderboard = Leaderboard::default();
+        leaderboard.add(CLASSIC, entry("Alice", 300));
+        leaderboard.add(CLASSIC, entry("Bob", 400));
+        let receipt = leaderboard.add(CLASSIC, entry("Alice", 200));
+        assert_eq!((receipt.rank, receipt.is_new_best), (2, false));
+        let receipt = leaderboard.add(CLASSIC, entry("Alice", 300));
+        assert!(!receipt.is_new_best);
+        let receipt = leaderboard.add(CLASSIC, entry("Alice", 600));
+        assert_eq!((receipt.rank, receipt.total, receipt.is_new_best), (1, 2, true));
+        let page = leaderboard.get_page(CLASSIC, 0, 10);
+        assert_eq!(page.entries.iter().map(| entry | entry.score).collect::<Vec<_>>(), [600, 400]);
+    }
+
+    #[test]
+    fn pages_start_at_the_offset() {
+        let mut leaderboard = Leaderboard::default();
+        for (index, player) in ["A", "B", "C", "D", "E"].iter().enumerate() {
+            leaderboard.add(CLASSIC, entry(player, 100 * (5 - index as Sze)));
+        }
+        let page = leaderboard.get_page(CLASSIC, 1, 2);
+        assert_eq!((page.offset, page.total), (1, 5));
+        assert_eq!(get_players(&page), ["B", "C"]);
+        assert!(leaderboard.get_page(CLASSIC, 10, 2).entries.is_empty());
+        let other_board = BoardId { difficulty: Difficulty::Hard, ..CLASSIC };
+        assert_eq!(leaderboard.get_page(other_board, 0, 10).total, 0);
+    }
+
+    #[test]
+    fn page_sizes_are_capped() {
+        let mut leaderboard = Leaderboard::default();
+        for index in 0..MAX_PAGE_SIZE + 5 {
+            leaderboard.add(CLASSIC, entry(&format!("Player {}", index), index as Sze));
+        }
+        let response = get_board_page(CLASSIC, "offset=2&limit=1000", &Mutex::new(leaderboard));
+        assert_eq!(response.status, 200);
+        let page: LeaderboardPage = ron::from_str(&response.body).unwrap();
+        assert_eq!((page.offset, page.entries.len(), page.total), (2, MAX_PAGE_SIZE, MAX_PAGE_SIZE + 5));
+    }
+}
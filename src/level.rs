@@ -0,0 +1,259 @@
+use std::{collections::BTreeMap, fmt, fs, io, path::Path};
+use serde::{Serialize, Deserialize};
+
+use crate::{cell::CellPos, grid::GRID_SIZE, portal::PortalPair, simulation::START_SNAKE_LENGHT, utils::get_stable_hash};
+
+const EMPTY_TILE: char = '.';
+const TIMED_WALL_TILE: char = 'W';
+const LAVA_TILE: char = 'L';
+const ROCK_TILE: char = '#';
+const FOOD_ZONE_TILE: char = 'F';
+const COMMENT_PREFIX: &str = "//";
+const DIRECTIVE_PREFIX: &str = "@";
+const PATROL_DIRECTIVE: &str = "@patrol";
+const START_DIRECTIVE: &str = "@start";
+/// The board is always `GRID_SIZE` cells wide, smaller boards are walled off with rocks
+pub const MIN_PLAYABLE_SIZE: usize = 7;
+/// Portal tiles go from `a` to `z`
+const MAX_PORTAL_PAIRS: usize = 26;
+
+/// A hand made or generated board layout
+///
+/// Level files are plain text with `GRID_SIZE` rows of `GRID_SIZE` tiles, the first row being the
+/// top of the board. Lines starting with `//` are ignored. Tiles are:
+///
+/// - `.` an empty cell
+/// - `a` to `z` a portal, every letter used must appear exactly twice to form a pair
+/// - `W` a wall that opens and closes on a timer
+/// - `L` a lava cell that spreads over time
+/// - `#` a rock that never moves
+/// - `F` a food zone, while any of them is empty food only spawns on food zones
+///
+/// Moving blocks are declared after the grid, one per line, as `@patrol x,y x,y ...` where each
+/// `x,y` is a waypoint (`0,0` being the bottom left cell). Consecutive waypoints must share a row
+/// or a column, and the block loops back to the first one after reaching the last.
+///
+/// An optional `@start x,y` line fixes where the snake head starts, the body trails to the left of
+/// it. Without it the head starts at a random cell.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub portals: Vec<PortalPair>,
+    pub timed_walls: Vec<CellPos>,
+    pub lava: Vec<CellPos>,
+    pub rocks: Vec<CellPos>,
+    pub food_zones: Vec<CellPos>,
+    pub patrols: Vec<Vec<CellPos>>,
+    pub start: Option<CellPos>
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(io::Error),
+    WrongSize { rows: usize, columns: usize },
+    UnknownTile(char),
+    UnpairedPortal(char),
+    InvalidPatrol(String),
+    InvalidStart(String),
+    UnknownDirective(String),
+    OutOfBounds(CellPos),
+    TooManyPortals(usize)
+}
+
+impl Level {
+    pub fn load(path: &Path) -> Result<Self, LevelError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_level_string())
+    }
+
+    pub fn parse(source: &str) -> Result<Self, LevelError> {
+        let lines: Vec<&str> = source
+            .lines()
+            .map(| line | line.trim())
+            .filter(| line | !line.is_empty() && !line.starts_with(COMMENT_PREFIX))
+            .collect();
+        let (directives, rows): (Vec<&str>, Vec<&str>) = lines
+            .iter()
+            .partition(| line | line.starts_with(DIRECTIVE_PREFIX));
+        if rows.len() != GRID_SIZE {
+            return Err(LevelError::WrongSize { rows: rows.len(), columns: GRID_SIZE });
+        }
+        let mut level = Level::default();
+        let mut portal_ends: BTreeMap<char, Vec<CellPos>> = BTreeMap::new();
+        for (row_index, row) in rows.iter().enumerate() {
+            let tiles: Vec<char> = row.chars().collect();
+            if tiles.len() != GRID_SIZE {
+                return Err(LevelError::WrongSize { rows: GRID_SIZE, columns: tiles.len() });
+            }
+            for (x, &tile) in tiles.iter().enumerate() {
+                let pos = CellPos { x, y: GRID_SIZE - 1 - row_index };
+                match tile {
+                    EMPTY_TILE => {},
+                    TIMED_WALL_TILE => level.timed_walls.push(pos),
+                    LAVA_TILE => level.lava.push(pos),
+                    ROCK_TILE => level.rocks.push(pos),
+                    FOOD_ZONE_TILE => level.food_zones.push(pos),
+                    'a'..='z' => portal_ends.entry(tile).or_default().push(pos),
+                    _ => return Err(LevelError::UnknownTile(tile))
+                }
+            }
+        }
+        for (tile, ends) in portal_ends {
+            if ends.len() != 2 {
+                return Err(LevelError::UnpairedPortal(tile));
+            }
+            level.portals.push(PortalPair { a: ends[0], b: ends[1] });
+        }
+        for directive in directives {
+            match directive.split_whitespace().next() {
+                Some(PATROL_DIRECTIVE) => level.patrols.push(parse_patrol(directive)?),
+                Some(START_DIRECTIVE) => level.start = Some(parse_start(directive)?),
+                _ => return Err(LevelError::UnknownDirective(directive.to_string()))
+            }
+        }
+        Ok(level)
+    }
+
+    /// Checks what `parse` makes sure of, for levels that were deserialized instead of parsed
+    pub fn validate(&self) -> Result<(), LevelError> {
+        let portal_ends = self.portals.iter().flat_map(| pair | [pair.a, pair.b]);
+        let tiles = self.timed_walls.iter().chain(&self.lava).chain(&self.rocks).chain(&self.food_zones).copied();
+        let waypoints = self.patrols.iter().flatten().copied();
+        if let Some(pos) = portal_ends.chain(tiles).chain(waypoints).chain(self.start).find(| pos | !is_on_board(*pos)) {
+            return Err(LevelError::OutOfBounds(pos));
+        }
+        if self.portals.len() > MAX_PORTAL_PAIRS {
+            return Err(LevelError::TooManyPortals(self.portals.len()));
+        }
+        if let Some(pair_id) = self.portals.iter().position(| pair | pair.a == pair.b) {
+            return Err(LevelError::UnpairedPortal(get_portal_tile(pair_id)));
+        }
+        if let Some(waypoints) = self.patrols.iter().find(| waypoints | !is_valid_patrol(waypoints)) {
+            return Err(LevelError::InvalidPatrol(get_patrol_directive(waypoints)));
+        }
+        match self.start {
+            Some(start) if !is_valid_start(start) => Err(LevelError::InvalidStart(get_start_directive(start))),
+            _ => Ok(())
+        }
+    }
+
+    /// Tells levels apart, the same on every build
+    pub fn get_hash(&self) -> u64 { get_stable_hash(self.to_level_string().as_bytes()) }
+
+    /// Empties a tile, removing both ends of a portal pair if `pos` is one of them
+    ///
+    /// Patrols are left alone.
+    pub fn clear_tile(&mut self, pos: CellPos) {
+        self.portals.retain(| pair | pair.a != pos && pair.b != pos);
+        self.timed_walls.retain(| &wall | wall != pos);
+        self.lava.retain(| &lava | lava != pos);
+        self.rocks.retain(| &rock | rock != pos);
+        self.food_zones.retain(| &zone | zone != pos);
+        if self.start == Some(pos) {
+            self.start = None;
+        }
+    }
+
+    pub fn to_level_string(&self) -> String {
+        let mut tiles = [[EMPTY_TILE; GRID_SIZE]; GRID_SIZE];
+        let mut set_tile = | pos: CellPos, tile: char | tiles[GRID_SIZE - 1 - pos.y][pos.x] = tile;
+        for (pair_id, pair) in self.portals.iter().enumerate() {
+            let tile = get_portal_tile(pair_id);
+            set_tile(pair.a, tile);
+            set_tile(pair.b, tile);
+        }
+        self.timed_walls.iter().for_each(| &pos | set_tile(pos, TIMED_WALL_TILE));
+        self.lava.iter().for_each(| &pos | set_tile(pos, LAVA_TILE));
+        self.rocks.iter().for_each(| &pos | set_tile(pos, ROCK_TILE));
+        self.food_zones.iter().for_each(| &pos | set_tile(pos, FOOD_ZONE_TILE));
+        let mut level_string: String = tiles.iter().map(| row | row.iter().collect::<String>() + "\n").collect();
+        for waypoints in self.patrols.iter() {
+            level_string += &get_patrol_directive(waypoints);
+            level_string += "\n";
+        }
+        if let Some(start) = self.start {
+            level_string += &get_start_directive(start);
+            level_string += "\n";
+        }
+        level_string
+    }
+}
+
+fn get_patrol_directive(waypoints: &[CellPos]) -> String {
+    let waypoints: Vec<String> = waypoints.iter().map(| pos | format!("{},{}", pos.x, pos.y)).collect();
+    format!("{} {}", PATROL_DIRECTIVE, waypoints.join(" "))
+}
+
+fn get_start_directive(start: CellPos) -> String { format!("{} {},{}", START_DIRECTIVE, start.x, start.y) }
+
+fn parse_patrol(directive: &str) -> Result<Vec<CellPos>, LevelError> {
+    let invalid = || LevelError::InvalidPatrol(directive.to_string());
+    let mut waypoints = vec!();
+    for waypoint in directive.split_whitespace().skip(1) {
+        waypoints.push(parse_cell_pos(waypoint).ok_or_else(invalid)?);
+    }
+    if !is_valid_patrol(&waypoints) {
+        return Err(invalid());
+    }
+    Ok(waypoints)
+}
+
+/// At least one waypoint and every one sharing a row or a column with the next
+fn is_valid_patrol(waypoints: &[CellPos]) -> bool {
+    let is_aligned = (0..waypoints.len()).all(| index | {
+        let (from, to) = (waypoints[index], waypoints[(index + 1) % waypoints.len()]);
+        from.x == to.x || from.y == to.y
+    });
+    !waypoints.is_empty() && is_aligned
+}
+
+/// Rock rings around a centered `playable_size` by `playable_size` square
+pub fn get_border_width(playable_size: usize) -> usize { (GRID_SIZE - playable_size) / 2 }
+
+/// How many cells away from the edge of the board `pos` is
+pub fn get_ring(pos: CellPos) -> usize { pos.x.min(pos.y).min(GRID_SIZE - 1 - pos.x).min(GRID_SIZE - 1 - pos.y) }
+
+/// The whole body has to fit on the board with at least one free cell in front of the head
+pub fn is_valid_start(pos: CellPos) -> bool { pos.x + 1 >= START_SNAKE_LENGHT && pos.x + 1 < GRID_SIZE }
+
+fn parse_start(directive: &str) -> Result<CellPos, LevelError> {
+    let mut args = directive.split_whitespace().skip(1);
+    match (args.next().and_then(parse_cell_pos), args.next()) {
+        (Some(pos), None) if is_valid_start(pos) => Ok(pos),
+        _ => Err(LevelError::InvalidStart(directive.to_string()))
+    }
+}
+
+fn parse_cell_pos(coordinates: &str) -> Option<CellPos> {
+    let (x, y) = coordinates.split_once(',')?;
+    let pos = CellPos { x: x.parse().ok()?, y: y.parse().ok()? };
+    is_on_board(pos).then_some(pos)
+}
+
+fn is_on_board(pos: CellPos) -> bool { pos.x < GRID_SIZE && pos.y < GRID_SIZE }
+
+fn get_portal_tile(pair_id: usize) -> char { (b'a' + (pair_id % 26) as u8) as char }
+
+impl From<io::Error> for LevelError {
+    fn from(error: io::Error) -> Self { LevelError::Io(error) }
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "could not read level file: {}", error),
+            Self::WrongSize { rows, columns } => write!(
+                f, "level must be {}x{} tiles, found {} rows of {}", GRID_SIZE, GRID_SIZE, rows, columns
+            ),
+            Self::UnknownTile(tile) => write!(f, "unknown tile '{}'", tile),
+            Self::UnpairedPortal(tile) => write!(f, "portal '{}' must appear exactly twice", tile),
+            Self::InvalidPatrol(line) => write!(f, "invalid patrol '{}'", line),
+            Self::InvalidStart(line) => write!(f, "invalid start '{}'", line),
+            Self::UnknownDirective(line) => write!(f, "unknown directive '{}'", line),
+            Self::OutOfBounds(pos) => write!(f, "cell {},{} is off the board", pos.x, pos.y),
+            Self::TooManyPortals(count) => write!(f, "{} portal pairs, at most {} fit", count, MAX_PORTAL_PAIRS)
+        }
+    }
+}
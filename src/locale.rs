@@ -0,0 +1,243 @@
+use std::{fs, path::{Path, PathBuf}};
+use ab_glyph::Font as _;
+use bevy::{asset::FileAssetIo, prelude::{Resource, Handle, Assets, AssetServer}, text::Font};
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue, concurrent::FluentBundle};
+use unic_langid::LanguageIdentifier;
+
+/// Relative to the assets folder, the first font tried for every language
+pub const FONT_PATH: &str = "fonts/FiraMono-Medium.ttf";
+/// Relative to the assets folder, Noto Sans JP cut down to ASCII and the characters of `ja.ftl`,
+/// tried right after `FONT_PATH`
+const JAPANESE_FONT_PATH: &str = "fonts/NotoSansJP-Subset.otf";
+
+pub static LANGUAGES: [Language; 3] = [
+    Language { code: "en", messages: include_str!("../assets/locales/en.ftl") },
+    Language { code: "de", messages: include_str!("../assets/locales/de.ftl") },
+    Language { code: "ja", messages: include_str!("../assets/locales/ja.ftl") }
+];
+
+/// Fonts with a wide coverage that are commonly installed, looked for by file name in `SYSTEM_FONT_DIRS`
+/// when the fonts in the assets don't cover a language
+const SYSTEM_FONT_NAMES: [&str; 14] = [
+    "NotoSansCJK-Regular.ttc",
+    "NotoSansCJKjp-Regular.otf",
+    "NotoSansJP-Regular.ttf",
+    "NotoSans-Regular.ttf",
+    "DroidSansFallbackFull.ttf",
+    "DroidSansFallback.ttf",
+    "DejaVuSansMono.ttf",
+    "DejaVuSans.ttf",
+    "Hiragino Sans GB.ttc",
+    "Arial Unicode.ttf",
+    "YuGothM.ttc",
+    "msgothic.ttc",
+    "meiryo.ttc",
+    "seguisym.ttf"
+];
+const SYSTEM_FONT_DIRS: [&str; 5] = [
+    "/usr/share/fonts",
+    "/usr/local/share/fonts",
+    "/System/Library/Fonts",
+    "/Library/Fonts",
+    "C:\\Windows\\Fonts"
+];
+/// Relative to the home folder
+const USER_FONT_DIRS: [&str; 3] = [".local/share/fonts", ".fonts", "Library/Fonts"];
+
+/// A language the UI is translated to, `messages` is its Fluent file from `assets/locales`
+pub struct Language {
+    /// Also the name of the message file
+    pub code: &'static str,
+    pub messages: &'static str
+}
+
+/// The UI strings of the current language and a font that can draw all of them
+#[derive(Resource)]
+pub struct Locale {
+    language: &'static Language,
+    bundle: FluentBundle<FluentResource>,
+    /// English, for messages the language doesn't have yet
+    fallback: FluentBundle<FluentResource>,
+    font: Handle<Font>
+}
+
+impl Language {
+    pub fn next(&'static self) -> &'static Language {
+        let index = LANGUAGES.iter().position(| language | std::ptr::eq(language, self)).unwrap_or(0);
+        &LANGUAGES[(index + 1) % LANGUAGES.len()]
+    }
+}
+
+/// `code` is a language like `de` or a locale like `de_AT.UTF-8`
+pub fn find_language(code: &str) -> Option<&'static Language> {
+    let code = code.split(['_', '-', '.']).next()?.to_lowercase();
+    LANGUAGES.iter().find(| language | language.code == code)
+}
+
+/// The language of the system if there is a translation for it, English otherwise
+pub fn get_system_language() -> &'static Language {
+    ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(| name | std::env::var(name).ok())
+        .find(| value | !value.is_empty())
+        .and_then(| value | find_language(&value))
+        .unwrap_or(&LANGUAGES[0])
+}
+
+impl Locale {
+    pub fn new(language: &'static Language, asset_server: &AssetServer, fonts: &mut Assets<Font>) -> Self {
+        Locale { font: load_font(language, asset_server, fonts), ..Self::without_font(language) }
+    }
+
+    /// Only the messages, for text printed to the console
+    pub fn without_font(language: &'static Language) -> Self {
+        Locale {
+            language,
+            bundle: get_bundle(language),
+            fallback: get_bundle(&LANGUAGES[0]),
+            font: Handle::default()
+        }
+    }
+
+    pub fn set_language(&mut self, language: &'static Language, asset_server: &AssetServer, fonts: &mut Assets<Font>) {
+        if !std::ptr::eq(self.language, language) {
+            *self = Self::new(language, asset_server, fonts);
+        }
+    }
+
+    pub fn get_language(&self) -> &'static Language { self.language }
+
+    pub fn get_font(&self) -> Handle<Font> { self.font.clone() }
+
+    pub fn get(&self, id: &str) -> String { self.format(id, &[]) }
+
+    /// Fills in the `{ $name }` placeables of the message from `args`, a missing message shows its id
+    pub fn format(&self, id: &str, args: &[(&str, FluentValue)]) -> String {
+        let args: FluentArgs = args.iter().map(| (name, value) | (*name, value.clone())).collect();
+        [&self.bundle, &self.fallback].into_iter()
+            .find_map(| bundle | {
+                let pattern = bundle.get_message(id)?.value()?;
+                Some(bundle.format_pattern(pattern, Some(&args), &mut vec!()).into_owned())
+            })
+            .unwrap_or_else(| | id.to_string())
+    }
+
+    /// Like `get` for the `.attribute` of a message
+    pub fn get_attribute(&self, id: &str, attribute: &str) -> String {
+        [&self.bundle, &self.fallback].into_iter()
+            .find_map(| bundle | {
+                let pattern = bundle.get_message(id)?.get_attribute(attribute)?.value();
+                Some(bundle.format_pattern(pattern, None, &mut vec!()).into_owned())
+            })
+            .unwrap_or_else(| | format!("{}.{}", id, attribute))
+    }
+}
+
+fn get_bundle(language: &Language) -> FluentBundle<FluentResource> {
+    let id: LanguageIdentifier = language.code.parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec!(id));
+    // The marks around placeables are for right to left scripts and show up as boxes in FiraMono
+    bundle.set_use_isolating(false);
+    let resource = FluentResource::try_new(language.messages.to_string()).unwrap_or_else(| (resource, errors) | {
+        eprintln!("Skipping {} broken messages in {}.ftl: {:?}", errors.len(), language.code, errors);
+        resource
+    });
+    if let Err(errors) = bundle.add_resource(resource) {
+        eprintln!("Duplicate messages in {}.ftl: {:?}", language.code, errors);
+    }
+    bundle
+}
+
+/// Finds the first font that has a glyph for every character of the language's messages
+///
+/// The fonts in the assets are tried first, FiraMono before the others, then the common system
+/// fonts. If none covers everything the one missing the fewest characters is used.
+fn load_font(language: &Language, asset_server: &AssetServer, fonts: &mut Assets<Font>) -> Handle<Font> {
+    let assets_dir = FileAssetIo::get_base_path().join("assets");
+    let mut characters: Vec<char> = language.messages.chars().filter(| c | !c.is_control()).collect();
+    characters.sort_unstable();
+    characters.dedup();
+    let mut best: Option<(usize, PathBuf, Vec<u8>)> = None;
+    for path in get_font_candidates(&assets_dir) {
+        let Ok(data) = fs::read(&path) else {
+            continue;
+        };
+        let Ok(font) = ab_glyph::FontRef::try_from_slice(&data) else {
+            continue;
+        };
+        let missing = characters.iter().filter(| c | font.glyph_id(**c).0 == 0).count();
+        if best.as_ref().map_or(true, | (best_missing, _, _) | missing < *best_missing) {
+            best = Some((missing, path, data));
+        }
+        if missing == 0 {
+            break;
+        }
+    }
+    match best {
+        Some((missing, path, data)) => {
+            if missing > 0 {
+                eprintln!("No font has every character of {}.ftl, {} are missing in {}", language.code, missing, path.display());
+            }
+            if path == assets_dir.join(FONT_PATH) {
+                return asset_server.load(FONT_PATH);
+            }
+            match Font::try_from_bytes(data) {
+                Ok(font) => fonts.add(font),
+                Err(error) => {
+                    eprintln!("Couldn't load {}: {}", path.display(), error);
+                    asset_server.load(FONT_PATH)
+                }
+            }
+        },
+        None => asset_server.load(FONT_PATH)
+    }
+}
+
+/// FiraMono, the Japanese subset, the other fonts in the assets and then the system fonts, which
+/// are only looked for once the assets had none that fits
+fn get_font_candidates(assets_dir: &Path) -> impl Iterator<Item = PathBuf> {
+    let primary = assets_dir.join(FONT_PATH);
+    let japanese = assets_dir.join(JAPANESE_FONT_PATH);
+    let mut asset_fonts: Vec<PathBuf> = primary.parent()
+        .and_then(| dir | fs::read_dir(dir).ok())
+        .map(| entries | entries.filter_map(| entry | entry.ok().map(| entry | entry.path())).collect())
+        .unwrap_or_default();
+    asset_fonts.sort_by_key(| path | (*path != japanese, path.clone()));
+    asset_fonts.retain(| path | *path != primary);
+    std::iter::once(primary)
+        .chain(asset_fonts)
+        .chain(std::iter::once(()).flat_map(| _ | get_system_fonts()))
+}
+
+fn get_system_fonts() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").or_else(| | std::env::var_os("USERPROFILE")).map(PathBuf::from);
+    let dirs = SYSTEM_FONT_DIRS.iter()
+        .map(PathBuf::from)
+        .chain(home.iter().flat_map(| home | USER_FONT_DIRS.iter().map(| dir | home.join(dir))));
+    let mut fonts = vec!();
+    for dir in dirs {
+        find_system_fonts(&dir, 0, &mut fonts);
+    }
+    fonts.sort_by_key(| path | {
+        let file_name = path.file_name().and_then(| name | name.to_str()).unwrap_or_default();
+        SYSTEM_FONT_NAMES.iter().position(| name | *name == file_name)
+    });
+    fonts
+}
+
+/// Collects the fonts named in `SYSTEM_FONT_NAMES` below `dir`
+fn find_system_fonts(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    // Font folders are only ever a few levels deep, this keeps a symlink loop from going on forever
+    if depth > 4 {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(| entry | entry.ok().map(| entry | entry.path())) {
+        if path.is_dir() {
+            find_system_fonts(&path, depth + 1, found);
+        } else if path.file_name().and_then(| name | name.to_str()).is_some_and(| name | SYSTEM_FONT_NAMES.contains(&name)) {
+            found.push(path);
+        }
+    }
+}
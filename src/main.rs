@@ -1,4 +1,5 @@
 mod cell;
+mod food;
 mod grid;
 mod text;
 mod utils;
@@ -6,20 +7,69 @@ mod input;
 mod render;
 mod timers;
 mod simulation;
+mod menu;
+mod settings;
+mod powerup;
+mod replay;
+mod portal;
+mod level;
+mod hazard;
+mod rules;
+mod daily;
+mod generator;
+mod editor;
+mod save;
+mod rewind;
+mod ghost;
+mod stats;
+mod difficulty;
+mod combo;
+mod achievements;
+mod netplay;
+mod spectate;
+mod leaderboard;
+mod locale;
 
+use std::time::Duration;
 use bevy::prelude::*;
 use bevy::DefaultPlugins;
-use bevy::sprite::MaterialMesh2dBundle;
+use bevy::window::{WindowFocused, PrimaryWindow};
+use bevy::app::AppExit;
+use serde::{Serialize, Deserialize};
 
 use crate::{
-    timers::StepTimers,
+    cell::CellPos,
+    daily::DailySession,
+    editor::{LevelEditor, EDITOR_TOOLS, DEFAULT_EDITOR_LEVEL_PATH},
+    save::SavedGame,
+    rewind::RewindBuffer,
+    ghost::Ghost,
+    stats::Stats,
+    difficulty::Difficulty,
+    achievements::AchievementTracker,
+    netplay::{VersusMatch, VersusConfig, VersusRole, DEFAULT_INPUT_DELAY_TICKS},
+    spectate::{SpectatorServer, SpectatorClient, ViewerPlugin, DEFAULT_SPECTATOR_PORT},
+    leaderboard::{LeaderboardClient, LeaderboardConfig, DEFAULT_LEADERBOARD_PORT, DEFAULT_LEADERBOARD_PATH, DEFAULT_PAGE_SIZE},
+    locale::{Locale, Language, find_language, get_system_language},
+    ghost::BestRun,
+    food::FoodKind,
+    hazard::HazardConfig,
+    level::Level,
+    powerup::PowerUpKind,
+    rules::TimeAttackRules,
+    timers::{StepTimers, ResumeCountdown, ToastTimer, SIM_TIMESTEP_SECONDS, MAX_CATCH_UP_SECONDS},
     input::{
         PlayerInput,
         handle_player_input,
-        RESTART_GAME_KEY, START_GAME_KEY, PAUSE_GAME_KEY
+        RESTART_GAME_KEY, START_GAME_KEY, PAUSE_GAME_KEY,
+        DAILY_GAME_KEY, DAILY_PRACTICE_KEY, CONTINUE_GAME_KEY, REWIND_KEY, STATS_KEY, ACHIEVEMENTS_KEY, LANGUAGE_KEY,
+        MENU_UP_KEY, MENU_DOWN_KEY, MENU_SELECT_KEY,
+        EDITOR_KEY, EDITOR_SAVE_KEY, EDITOR_LOAD_KEY, EDITOR_GROW_KEY, EDITOR_SHRINK_KEY, EDITOR_TOOL_KEYS
     },
-    render::{render_game, get_background_shape},
-    simulation::Sim,
+    menu::{PauseMenu, PauseAction},
+    render::{render_game, render_stamina, render_combo, render_level_editor, render_stats, spawn_background, translate_screen_pos_to_grid},
+    settings::Settings,
+    simulation::{Sim, EndReason},
     text::SnakeTexts,
     utils::Direction
 };
@@ -35,18 +85,48 @@ struct WinSound(Handle<AudioSource>);
 
 pub type Sze = u32;
 
-#[derive(Event, Debug)]
+/// Sent once when a game ends, `pos` is the cell where it happened
+#[derive(Event, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct SimulationOver {
-    win: bool
+    pub reason: EndReason,
+    pub pos: CellPos,
+    pub tick: Sze
+}
+
+/// Sent after every step of the game with the state observers like achievements look at
+#[derive(Event, Clone, Copy)]
+pub struct SimulationTicked {
+    /// Changes when a new game starts, restarts on the same seed go back in ticks instead
+    pub seed: u64,
+    pub tick: Sze,
+    pub score: Sze,
+    pub snake_length: Sze,
+    /// Counts the pieces the snake started with too
+    pub eaten_food: Sze,
+    pub is_boosting: bool,
+    pub combo_chain: Sze
 }
 
 #[derive(Event)]
 pub struct FoodEaten {
     pub pieces_eaten: Sze,
-    pub new_score: Sze
+    pub new_score: Sze,
+    pub food_kind: FoodKind
 }
 
-pub struct SnakePlugin;
+pub struct SnakePlugin {
+    pub level: Option<Level>,
+    /// Where the level editor saves to and loads from
+    pub editor_path: std::path::PathBuf,
+    /// Plays a single versus match over the network instead of the menus
+    pub versus: Option<VersusConfig>,
+    /// Streams the game to viewers on this port
+    pub spectator_port: Option<u16>,
+    /// Uploads ranked games when they end
+    pub leaderboard: Option<LeaderboardConfig>,
+    /// Until it's changed in the settings
+    pub language: &'static Language
+}
 
 #[derive(Resource)]
 struct Game {
@@ -57,110 +137,809 @@ pub enum GameState {
     StartMenu,
     SimulationRunning,
     Paused,
+    ResumeCountdown,
     GameOverMenu,
+    LevelEditor,
+    Stats,
+    Achievements,
 }
 
+#[allow(clippy::too_many_arguments)]
 fn setup(
     asset_server: Res<AssetServer>,
+    daily: Res<DailySession>,
+    settings: Res<Settings>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    mut fonts: ResMut<Assets<Font>>,
     mut texts: ResMut<SnakeTexts>,
 ) {
     commands.spawn(Camera2dBundle::default());
-    commands.spawn(MaterialMesh2dBundle {
-        mesh: meshes.add(get_background_shape().into()).into(),
-        material: materials.add(ColorMaterial::from(Color::Rgba { red: 0.1, green: 0.7, blue: 0.2, alpha: 0.7 })),
-        ..default()
-    });
+    spawn_background(&mut commands, &mut meshes, &mut materials);
     commands.insert_resource(BiteSound(asset_server.load("audio/bite.ogg")));
     commands.insert_resource(GameOverSound(asset_server.load("audio/gameover.ogg")));
     commands.insert_resource(WinSound(asset_server.load("audio/win.ogg")));
-    texts.initialize(commands, asset_server);
+    let locale = Locale::new(settings.language, &asset_server, &mut fonts);
+    texts.initialize(&mut commands, &locale, &daily);
+    commands.insert_resource(locale);
 }
 
+#[allow(clippy::too_many_arguments)]
 fn update_start_menu(
-    commands: Commands,
-    keyboard_input: Res<Input<KeyCode>>,
+    mut commands: Commands,
+    locale: Res<Locale>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
     mut game: ResMut<Game>,
     mut texts: ResMut<SnakeTexts>,
+    mut simulation: ResMut<Sim>,
+    mut daily: ResMut<DailySession>,
+    mut step_timers: ResMut<StepTimers>,
+    mut player_input: ResMut<PlayerInput>,
+    mut countdown: ResMut<ResumeCountdown>,
+    editor: Res<LevelEditor>,
+    stats: Res<Stats>,
+    achievements: Res<AchievementTracker>,
+    versus: Option<Res<VersusMatch>>,
 ) {
-    let on_start_menu = matches!(game.state, GameState::StartMenu);
-    if on_start_menu && keyboard_input.just_pressed(START_GAME_KEY.keycode) {
-        texts.despawn_start_menu(commands);
-        game.state = GameState::SimulationRunning;
+    if !matches!(game.state, GameState::StartMenu) || versus.is_some() {
+        return;
+    }
+    if keyboard_input.just_pressed(STATS_KEY.keycode) {
+        keyboard_input.clear_just_pressed(STATS_KEY.keycode);
+        texts.despawn_start_menu(&mut commands);
+        texts.spawn_stats_screen(&mut commands, &locale, &stats);
+        game.state = GameState::Stats;
+        return;
+    }
+    if keyboard_input.just_pressed(ACHIEVEMENTS_KEY.keycode) {
+        keyboard_input.clear_just_pressed(ACHIEVEMENTS_KEY.keycode);
+        texts.despawn_start_menu(&mut commands);
+        texts.spawn_achievements_screen(&mut commands, &locale, &achievements);
+        game.state = GameState::Achievements;
+        return;
     }
+    if keyboard_input.just_pressed(CONTINUE_GAME_KEY.keycode) && SavedGame::exists() {
+        texts.despawn_start_menu(&mut commands);
+        let loaded_game = SavedGame::load();
+        SavedGame::delete();
+        match loaded_game {
+            Ok(saved_game) => {
+                saved_game.restore(&mut simulation, &mut step_timers, &mut player_input);
+                countdown.start();
+                texts.spawn_countdown_text(&mut commands, &locale, countdown.get_remaining());
+                game.state = GameState::ResumeCountdown;
+            },
+            Err(error) => {
+                eprintln!("Discarding saved game: {}", error);
+                texts.spawn_start_menu(&mut commands, &locale, &daily);
+            }
+        }
+        return;
+    }
+    if keyboard_input.just_pressed(EDITOR_KEY.keycode) {
+        keyboard_input.clear_just_pressed(EDITOR_KEY.keycode);
+        texts.despawn_start_menu(&mut commands);
+        open_level_editor(&mut commands, &locale, &mut game, &mut texts, &editor);
+        return;
+    }
+    let daily_practice = keyboard_input.just_pressed(DAILY_PRACTICE_KEY.keycode);
+    if daily_practice || keyboard_input.just_pressed(DAILY_GAME_KEY.keycode) {
+        daily.start(&mut simulation, daily_practice);
+        reset_controls(&mut step_timers, &mut player_input, simulation.get_difficulty());
+    } else if !keyboard_input.just_pressed(START_GAME_KEY.keycode) {
+        return;
+    }
+    texts.despawn_start_menu(&mut commands);
+    game.state = GameState::SimulationRunning;
 }
 
+#[allow(clippy::too_many_arguments)]
 fn update_pause_menu(
-    commands: Commands,
-    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+    locale: Res<Locale>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut game: ResMut<Game>,
+    mut texts: ResMut<SnakeTexts>,
+    mut pause_menu: ResMut<PauseMenu>,
+    mut settings: ResMut<Settings>,
+    mut countdown: ResMut<ResumeCountdown>,
+    mut simulation: ResMut<Sim>,
+    mut step_timers: ResMut<StepTimers>,
+    mut player_input: ResMut<PlayerInput>,
+    mut daily: ResMut<DailySession>,
+    mut editor: ResMut<LevelEditor>,
+    versus: Option<Res<VersusMatch>>,
+) {
+    match game.state {
+        GameState::SimulationRunning => {
+            // The peer would have to wait for the whole pause
+            if keyboard_input.just_pressed(PAUSE_GAME_KEY.keycode) && versus.is_none() {
+                open_pause_menu(&mut commands, &locale, &mut game, &mut texts, &mut pause_menu, &settings);
+                keyboard_input.clear_just_pressed(PAUSE_GAME_KEY.keycode);
+            }
+        },
+        GameState::Paused => {
+            let action = if keyboard_input.just_pressed(PAUSE_GAME_KEY.keycode) {
+                keyboard_input.clear_just_pressed(PAUSE_GAME_KEY.keycode);
+                Some(PauseAction::Resume)
+            } else if keyboard_input.just_pressed(MENU_SELECT_KEY.keycode) {
+                keyboard_input.clear_just_pressed(MENU_SELECT_KEY.keycode);
+                let rule_settings = settings.get_rule_settings();
+                let action = pause_menu.confirm(&mut settings);
+                if !daily.is_active() && settings.get_rule_settings() != rule_settings {
+                    apply_settings(&mut simulation, &settings);
+                }
+                Some(action)
+            } else if keyboard_input.just_pressed(MENU_UP_KEY.keycode) {
+                pause_menu.select_previous();
+                Some(PauseAction::Redraw)
+            } else if keyboard_input.just_pressed(MENU_DOWN_KEY.keycode) {
+                pause_menu.select_next();
+                Some(PauseAction::Redraw)
+            } else {
+                None
+            };
+            match action {
+                Some(PauseAction::Resume) => {
+                    texts.despawn_pause_menu(&mut commands);
+                    start_resume_countdown(&mut commands, &locale, &mut game, &mut texts, &mut countdown, &mut player_input);
+                },
+                Some(PauseAction::Restart) => {
+                    texts.despawn_pause_menu(&mut commands);
+                    start_resume_countdown(&mut commands, &locale, &mut game, &mut texts, &mut countdown, &mut player_input);
+                    reset_game(&mut simulation, &mut step_timers, &mut player_input, &mut daily);
+                },
+                Some(PauseAction::SaveGame) => {
+                    pause_menu.set_message(match SavedGame::save(&simulation, &step_timers, &player_input) {
+                        Ok(()) => locale.get("game-saved"),
+                        Err(error) => locale.format("game-save-failed", &[("error", error.to_string().into())])
+                    });
+                    texts.despawn_pause_menu(&mut commands);
+                    texts.spawn_pause_menu(&mut commands, &locale, &pause_menu, &settings);
+                },
+                Some(PauseAction::QuitToMainMenu) => {
+                    texts.despawn_pause_menu(&mut commands);
+                    if editor.stop_test_play(&mut simulation) {
+                        reset_game(&mut simulation, &mut step_timers, &mut player_input, &mut daily);
+                        open_level_editor(&mut commands, &locale, &mut game, &mut texts, &editor);
+                        return;
+                    }
+                    save_game_in_progress(&simulation, &step_timers, &player_input, &daily);
+                    if daily.is_active() {
+                        daily.leave(&mut simulation);
+                        apply_settings(&mut simulation, &settings);
+                    }
+                    reset_game(&mut simulation, &mut step_timers, &mut player_input, &mut daily);
+                    texts.spawn_start_menu(&mut commands, &locale, &daily);
+                    game.state = GameState::StartMenu;
+                },
+                Some(PauseAction::Redraw) => {
+                    texts.despawn_pause_menu(&mut commands);
+                    texts.spawn_pause_menu(&mut commands, &locale, &pause_menu, &settings);
+                },
+                None => {}
+            }
+        },
+        _ => {}
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_level_editor(
+    mut commands: Commands,
+    locale: Res<Locale>,
     mut keyboard_input: ResMut<Input<KeyCode>>,
     mut game: ResMut<Game>,
     mut texts: ResMut<SnakeTexts>,
+    mut editor: ResMut<LevelEditor>,
+    mut simulation: ResMut<Sim>,
+    mut step_timers: ResMut<StepTimers>,
+    mut player_input: ResMut<PlayerInput>,
+    mut daily: ResMut<DailySession>,
 ) {
-    if keyboard_input.just_pressed(PAUSE_GAME_KEY.keycode) {
-        if matches!(game.state, GameState::SimulationRunning) {
-            texts.spawn_paused_text(commands, asset_server);
-            game.state = GameState::Paused;
-            keyboard_input.clear_just_pressed(PAUSE_GAME_KEY.keycode);
-        } else if matches!(game.state, GameState::Paused) {
-            texts.despawn_paused_text(commands);
+    if !matches!(game.state, GameState::LevelEditor) {
+        return;
+    }
+    if let Some(tool_index) = EDITOR_TOOL_KEYS.iter().position(| &key | keyboard_input.just_pressed(key)) {
+        editor.select_tool(EDITOR_TOOLS[tool_index]);
+    } else if keyboard_input.just_pressed(EDITOR_GROW_KEY.keycode) {
+        editor.grow_board();
+    } else if keyboard_input.just_pressed(EDITOR_SHRINK_KEY.keycode) {
+        editor.shrink_board();
+    } else if keyboard_input.just_pressed(EDITOR_SAVE_KEY.keycode) {
+        editor.save();
+    } else if keyboard_input.just_pressed(EDITOR_LOAD_KEY.keycode) {
+        editor.load();
+    } else if keyboard_input.just_pressed(MENU_SELECT_KEY.keycode) {
+        texts.despawn_editor_text(&mut commands);
+        editor.start_test_play(&mut simulation);
+        reset_game(&mut simulation, &mut step_timers, &mut player_input, &mut daily);
+        game.state = GameState::SimulationRunning;
+    } else if keyboard_input.just_pressed(EDITOR_KEY.keycode) {
+        keyboard_input.clear_just_pressed(EDITOR_KEY.keycode);
+        texts.despawn_editor_text(&mut commands);
+        texts.spawn_start_menu(&mut commands, &locale, &daily);
+        game.state = GameState::StartMenu;
+    }
+}
+
+fn paint_level_editor(
+    game: Res<Game>,
+    mouse_input: Res<Input<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut editor: ResMut<LevelEditor>,
+) {
+    if !matches!(game.state, GameState::LevelEditor) {
+        return;
+    }
+    let Some(cell_pos) = get_cursor_cell_pos(&windows, &cameras) else {
+        return;
+    };
+    if mouse_input.pressed(MouseButton::Left) {
+        editor.paint(cell_pos, mouse_input.just_pressed(MouseButton::Left));
+    } else if mouse_input.pressed(MouseButton::Right) {
+        editor.erase(cell_pos);
+    }
+}
+
+fn get_cursor_cell_pos(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    cameras: &Query<(&Camera, &GlobalTransform)>
+) -> Option<CellPos> {
+    let cursor_pos = windows.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = cameras.get_single().ok()?;
+    translate_screen_pos_to_grid(camera.viewport_to_world_2d(camera_transform, cursor_pos)?)
+}
+
+fn update_editor_text(
+    texts: Res<SnakeTexts>,
+    editor: Res<LevelEditor>,
+    locale: Res<Locale>,
+    mut text_query: Query<&mut Text>
+) {
+    if editor.is_changed() || locale.is_changed() {
+        if let Ok(mut text) = text_query.get_mut(texts.editor) {
+            text.sections[1].value = editor.get_description(&locale);
+        }
+    }
+}
+
+fn open_level_editor(
+    commands: &mut Commands,
+    locale: &Locale,
+    game: &mut Game,
+    texts: &mut SnakeTexts,
+    editor: &LevelEditor,
+) {
+    texts.spawn_editor_text(commands, locale, editor);
+    game.state = GameState::LevelEditor;
+}
+
+fn update_resume_countdown(
+    time: Res<Time>,
+    locale: Res<Locale>,
+    mut commands: Commands,
+    mut game: ResMut<Game>,
+    mut texts: ResMut<SnakeTexts>,
+    mut countdown: ResMut<ResumeCountdown>,
+) {
+    if matches!(game.state, GameState::ResumeCountdown) && countdown.tick(time.delta()) {
+        texts.despawn_countdown_text(&mut commands);
+        if countdown.is_finished() {
             game.state = GameState::SimulationRunning;
-            keyboard_input.clear_just_pressed(PAUSE_GAME_KEY.keycode);
+        } else {
+            texts.spawn_countdown_text(&mut commands, &locale, countdown.get_remaining());
         }
     }
 }
 
+fn pause_on_focus_lost(
+    locale: Res<Locale>,
+    settings: Res<Settings>,
+    mut commands: Commands,
+    mut game: ResMut<Game>,
+    mut texts: ResMut<SnakeTexts>,
+    mut pause_menu: ResMut<PauseMenu>,
+    mut focus_events: EventReader<WindowFocused>,
+    versus: Option<Res<VersusMatch>>,
+) {
+    let focus_lost = focus_events.iter().any(| event | !event.focused);
+    if !focus_lost || versus.is_some() {
+        return;
+    }
+    match game.state {
+        GameState::SimulationRunning => {
+            open_pause_menu(&mut commands, &locale, &mut game, &mut texts, &mut pause_menu, &settings);
+        },
+        GameState::ResumeCountdown => {
+            texts.despawn_countdown_text(&mut commands);
+            open_pause_menu(&mut commands, &locale, &mut game, &mut texts, &mut pause_menu, &settings);
+        },
+        _ => {}
+    }
+}
+
+fn open_pause_menu(
+    commands: &mut Commands,
+    locale: &Locale,
+    game: &mut Game,
+    texts: &mut SnakeTexts,
+    pause_menu: &mut PauseMenu,
+    settings: &Settings,
+) {
+    pause_menu.open();
+    texts.spawn_pause_menu(commands, locale, pause_menu, settings);
+    game.state = GameState::Paused;
+}
+
+fn start_resume_countdown(
+    commands: &mut Commands,
+    locale: &Locale,
+    game: &mut Game,
+    texts: &mut SnakeTexts,
+    countdown: &mut ResumeCountdown,
+    player_input: &mut PlayerInput,
+) {
+    countdown.start();
+    player_input.input_direction.clear();
+    texts.spawn_countdown_text(commands, locale, countdown.get_remaining());
+    game.state = GameState::ResumeCountdown;
+}
+
+fn reset_game(
+    simulation: &mut Sim,
+    step_timers: &mut StepTimers,
+    player_input: &mut PlayerInput,
+    daily: &mut DailySession
+) {
+    if daily.is_active() {
+        daily.reset_game(simulation);
+    } else {
+        simulation.reset_new_game();
+    }
+    reset_controls(step_timers, player_input, simulation.get_difficulty());
+}
+
+fn reset_controls(step_timers: &mut StepTimers, player_input: &mut PlayerInput, difficulty: Difficulty) {
+    step_timers.reset_tick_speed(difficulty);
+    player_input.input_direction.clear();
+    player_input.input_direction.push(Direction::Right);
+}
+
+/// Hands the settings that only apply to the next game over to the simulation
+fn apply_settings(simulation: &mut Sim, settings: &Settings) {
+    simulation.set_food_count(settings.food_count);
+    simulation.set_difficulty(settings.difficulty);
+    simulation.set_random_portal_pairs(settings.portal_pairs);
+    simulation.set_hazard_config(if settings.hazards_enabled {
+        HazardConfig::with_random_hazards()
+    } else {
+        HazardConfig::default()
+    });
+    simulation.set_time_attack(if settings.time_attack_enabled {
+        Some(TimeAttackRules::default())
+    } else {
+        None
+    });
+}
+
+/// Runs once per `FixedUpdate` period, so a slow frame runs as many steps as the time it took
+#[allow(clippy::too_many_arguments)]
 fn update_simulation(
-    time: Res<Time>,
+    fixed_time: Res<FixedTime>,
     menu: Res<Game>,
-    score_writer: EventWriter<FoodEaten>,
-    game_over_writer: EventWriter<SimulationOver>,
+    mut score_writer: EventWriter<FoodEaten>,
+    mut game_over_writer: EventWriter<SimulationOver>,
+    mut tick_writer: EventWriter<SimulationTicked>,
     mut simulation: ResMut<Sim>,
     mut step_timers: ResMut<StepTimers>,
     mut player_input: ResMut<PlayerInput>,
+    mut rewind: ResMut<RewindBuffer>,
+    mut ghost: ResMut<Ghost>,
+    versus: Option<Res<VersusMatch>>,
 ) {
-    if matches!(menu.state, GameState::SimulationRunning) {
-        let boost_timer_finished = step_timers.boost_timer.tick(time.delta()).just_finished();
-        let boost_active = boost_timer_finished && player_input.is_boost_active;
-        let tick_timer_finished = step_timers.tick_timer.tick(time.delta()).just_finished();
+    let is_over_catch_up_cap = fixed_time.accumulated() > Duration::from_secs_f64(MAX_CATCH_UP_SECONDS);
+    let is_running = matches!(menu.state, GameState::SimulationRunning) && versus.is_none();
+    if is_running && !rewind.is_rewinding() && !is_over_catch_up_cap {
+        let delta = if simulation.has_effect(PowerUpKind::SlowMotion) { fixed_time.period / 2 } else { fixed_time.period };
+        let steps = step_timers.advance(delta, player_input.is_boost_active);
 
-        if simulation.is_game_running() && (boost_active || tick_timer_finished) {
-            simulation.run_next_step(&mut player_input.input_direction, score_writer, game_over_writer);
+        for _ in 0..steps {
+            if !simulation.is_game_running() {
+                break;
+            }
+            rewind.record(&mut simulation, &step_timers, &mut ghost);
+            simulation.set_boosting(step_timers.is_boosting());
+            simulation.run_next_step(
+                &mut player_input.input_direction,
+                &mut score_writer,
+                &mut game_over_writer,
+                &mut tick_writer
+            );
         }
     }
 }
 
+/// Steps a versus match in place of `update_simulation`, as far as the peer's moves allow
+///
+/// The opponent keeps going after the local snake died, so this runs on the game over screen too.
+/// There is no boost in versus, the peer couldn't follow it.
+#[allow(clippy::too_many_arguments)]
+fn update_versus(
+    fixed_time: Res<FixedTime>,
+    menu: Res<Game>,
+    mut score_writer: EventWriter<FoodEaten>,
+    mut game_over_writer: EventWriter<SimulationOver>,
+    mut tick_writer: EventWriter<SimulationTicked>,
+    mut simulation: ResMut<Sim>,
+    mut step_timers: ResMut<StepTimers>,
+    mut player_input: ResMut<PlayerInput>,
+    versus: Option<ResMut<VersusMatch>>,
+) {
+    let Some(mut versus) = versus else {
+        return;
+    };
+    if !versus.is_started() || !matches!(menu.state, GameState::SimulationRunning | GameState::GameOverMenu) {
+        return;
+    }
+    versus.poll();
+    if fixed_time.accumulated() <= Duration::from_secs_f64(MAX_CATCH_UP_SECONDS) {
+        // Both sides tick at the match speed, slow motion would make one fall behind the other
+        step_timers.set_speed(versus.get_speed(&simulation));
+        let steps = step_timers.advance(fixed_time.period, false);
+        versus.run_due_steps(steps, &mut simulation, &mut player_input.input_direction, | simulation, input_direction | {
+            simulation.run_next_step(input_direction, &mut score_writer, &mut game_over_writer, &mut tick_writer);
+        });
+    }
+    versus.flush();
+}
+
+/// Waits on the start menu until the peer is there, then both games start together
+fn update_versus_lobby(
+    mut commands: Commands,
+    mut game: ResMut<Game>,
+    mut texts: ResMut<SnakeTexts>,
+    mut simulation: ResMut<Sim>,
+    mut step_timers: ResMut<StepTimers>,
+    mut player_input: ResMut<PlayerInput>,
+    versus: Option<ResMut<VersusMatch>>,
+) {
+    let Some(mut versus) = versus else {
+        return;
+    };
+    if matches!(game.state, GameState::StartMenu) && versus.try_start(&mut simulation) {
+        reset_controls(&mut step_timers, &mut player_input, simulation.get_difficulty());
+        texts.despawn_start_menu(&mut commands);
+        game.state = GameState::SimulationRunning;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn update_game_over_menu(
-    commands: Commands,
+    mut commands: Commands,
     keyboard_input: Res<Input<KeyCode>>,
     mut game: ResMut<Game>,
     mut texts: ResMut<SnakeTexts>,
     mut simulation: ResMut<Sim>,
     mut step_timers: ResMut<StepTimers>,
     mut player_input: ResMut<PlayerInput>,
+    mut daily: ResMut<DailySession>,
+    versus: Option<Res<VersusMatch>>,
 ) {
-    let on_game_over_menu = matches!(game.state, GameState::GameOverMenu);
+    let on_game_over_menu = matches!(game.state, GameState::GameOverMenu) && versus.is_none();
     if on_game_over_menu && keyboard_input.just_pressed(RESTART_GAME_KEY.keycode) {
-        texts.despawn_game_over_text(commands);
-        simulation.reset_new_game();
-        step_timers.reset_tick_speed();
-        player_input.input_direction.clear();
-        player_input.input_direction.push(Direction::Right);
+        texts.despawn_game_over_text(&mut commands);
+        reset_game(&mut simulation, &mut step_timers, &mut player_input, &mut daily);
         game.state = GameState::SimulationRunning;
     }
 }
 
+/// Steps back in time while the rewind key is held, out of the game over screen too
+#[allow(clippy::too_many_arguments)]
+fn update_rewind(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    settings: Res<Settings>,
+    daily: Res<DailySession>,
+    mut commands: Commands,
+    mut game: ResMut<Game>,
+    mut texts: ResMut<SnakeTexts>,
+    mut rewind: ResMut<RewindBuffer>,
+    mut simulation: ResMut<Sim>,
+    mut step_timers: ResMut<StepTimers>,
+    mut player_input: ResMut<PlayerInput>,
+    mut ghost: ResMut<Ghost>,
+    versus: Option<Res<VersusMatch>>,
+) {
+    let can_rewind = settings.practice_mode
+        && !daily.is_attempt()
+        && versus.is_none()
+        && matches!(game.state, GameState::SimulationRunning | GameState::GameOverMenu);
+    let is_step_due = rewind.update(can_rewind && keyboard_input.pressed(REWIND_KEY.keycode), time.delta());
+    if is_step_due && rewind.rewind_step(&mut simulation, &mut step_timers, &mut ghost) {
+        player_input.input_direction.clear();
+        if matches!(game.state, GameState::GameOverMenu) {
+            texts.despawn_game_over_text(&mut commands);
+            game.state = GameState::SimulationRunning;
+        }
+    }
+}
+
 fn update_score(
     texts: Res<SnakeTexts>,
-    mut events: EventReader<FoodEaten>,
+    simulation: Res<Sim>,
     mut score_text_query: Query<(Entity, &mut Text)>
 ) {
-    if !events.is_empty() {
+    if simulation.is_changed() {
         if let Ok(mut text) = score_text_query.get_component_mut::<Text>(texts.score) {
-            for event in events.iter() {
-                text.sections[0].value = event.new_score.to_string();
+            text.sections[0].value = simulation.get_score().to_string();
+        }
+    }
+}
+
+fn update_effects_text(
+    texts: Res<SnakeTexts>,
+    simulation: Res<Sim>,
+    locale: Res<Locale>,
+    mut text_query: Query<&mut Text>
+) {
+    if simulation.is_changed() || locale.is_changed() {
+        if let Ok(mut text) = text_query.get_mut(texts.effects) {
+            let combo_chain = simulation.get_combo().get_chain(simulation.get_tick());
+            let combo = (combo_chain > 1).then(| | {
+                locale.format("combo", &[("multiplier", simulation.get_combo().get_multiplier(simulation.get_tick()).into())])
+            });
+            text.sections[0].value = combo.into_iter()
+                .chain(simulation
+                    .get_active_effects()
+                    .iter()
+                    .map(| effect | format!("{} {}", locale.get(effect.kind.get_message_id()), effect.remaining_ticks)))
+                .collect::<Vec<String>>()
+                .join("  ");
+        }
+    }
+}
+
+fn update_speed_text(
+    texts: Res<SnakeTexts>,
+    step_timers: Res<StepTimers>,
+    locale: Res<Locale>,
+    mut text_query: Query<&mut Text>
+) {
+    if step_timers.is_changed() || locale.is_changed() {
+        if let Ok(mut text) = text_query.get_mut(texts.speed) {
+            let value = locale.format("speed", &[
+                ("difficulty", locale.get(step_timers.get_difficulty().get_message_id()).into()),
+                ("level", (step_timers.get_speed().level + 1).into())
+            ]);
+            if text.sections[0].value != value {
+                text.sections[0].value = value;
+            }
+        }
+    }
+}
+
+/// Counts the time attack clock in ticks, seconds would be a guess since ticks get shorter as the game speeds up
+fn update_timer_text(
+    texts: Res<SnakeTexts>,
+    simulation: Res<Sim>,
+    locale: Res<Locale>,
+    mut text_query: Query<&mut Text>
+) {
+    if simulation.is_changed() || locale.is_changed() {
+        if let Ok(mut text) = text_query.get_mut(texts.timer) {
+            text.sections[0].value = match simulation.get_remaining_ticks() {
+                Some(remaining_ticks) => locale.format("time-attack-ticks", &[("ticks", remaining_ticks.into())]),
+                None => String::new()
+            };
+        }
+    }
+}
+
+fn update_stats_screen(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut game: ResMut<Game>,
+    mut texts: ResMut<SnakeTexts>,
+    daily: Res<DailySession>,
+) {
+    let is_back_pressed = keyboard_input.any_just_pressed([STATS_KEY.keycode, MENU_SELECT_KEY.keycode]);
+    if matches!(game.state, GameState::Stats) && is_back_pressed {
+        texts.despawn_stats_screen(&mut commands);
+        texts.spawn_start_menu(&mut commands, &locale, &daily);
+        game.state = GameState::StartMenu;
+    }
+}
+
+fn update_achievements_screen(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut game: ResMut<Game>,
+    mut texts: ResMut<SnakeTexts>,
+    daily: Res<DailySession>,
+) {
+    let is_back_pressed = keyboard_input.any_just_pressed([ACHIEVEMENTS_KEY.keycode, MENU_SELECT_KEY.keycode]);
+    if matches!(game.state, GameState::Achievements) && is_back_pressed {
+        texts.despawn_achievements_screen(&mut commands);
+        texts.spawn_start_menu(&mut commands, &locale, &daily);
+        game.state = GameState::StartMenu;
+    }
+}
+
+/// Cycles the language from the start menu and switches to the one picked in the settings, the
+/// texts already on screen get the new font and the open menu is drawn again in the new language
+#[allow(clippy::too_many_arguments)]
+fn update_language(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    keyboard_input: Res<Input<KeyCode>>,
+    game: Res<Game>,
+    daily: Res<DailySession>,
+    pause_menu: Res<PauseMenu>,
+    mut settings: ResMut<Settings>,
+    mut locale: ResMut<Locale>,
+    mut fonts: ResMut<Assets<Font>>,
+    mut texts: ResMut<SnakeTexts>,
+    mut text_query: Query<&mut Text>,
+) {
+    if matches!(game.state, GameState::StartMenu) && keyboard_input.just_pressed(LANGUAGE_KEY.keycode) {
+        settings.cycle_language();
+    }
+    if std::ptr::eq(settings.language, locale.get_language()) {
+        return;
+    }
+    locale.set_language(settings.language, &asset_server, &mut fonts);
+    for mut text in text_query.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.style.font = locale.get_font();
+        }
+    }
+    match game.state {
+        GameState::StartMenu if texts.start != Entity::PLACEHOLDER => {
+            texts.despawn_start_menu(&mut commands);
+            texts.spawn_start_menu(&mut commands, &locale, &daily);
+        },
+        GameState::Paused => {
+            texts.despawn_pause_menu(&mut commands);
+            texts.spawn_pause_menu(&mut commands, &locale, &pause_menu, &settings);
+        },
+        _ => {}
+    }
+}
+
+/// Feeds the game events to the achievements
+fn update_achievements(
+    time: Res<Time>,
+    game: Res<Game>,
+    mut achievements: ResMut<AchievementTracker>,
+    mut tick_events: EventReader<SimulationTicked>,
+    mut food_events: EventReader<FoodEaten>,
+    mut game_over_events: EventReader<SimulationOver>,
+) {
+    if matches!(game.state, GameState::SimulationRunning) {
+        achievements.add_play_time(time.delta());
+    }
+    for event in tick_events.iter() {
+        achievements.on_tick(event);
+    }
+    for event in food_events.iter() {
+        achievements.on_food_eaten(event);
+    }
+    for event in game_over_events.iter() {
+        achievements.on_game_over(event);
+    }
+}
+
+/// Shows the unlocked achievements and the leaderboard results one after the other
+fn update_toasts(
+    mut commands: Commands,
+    locale: Res<Locale>,
+    time: Res<Time>,
+    mut texts: ResMut<SnakeTexts>,
+    mut achievements: ResMut<AchievementTracker>,
+    leaderboard: Option<Res<LeaderboardClient>>,
+    mut toast_timer: ResMut<ToastTimer>,
+) {
+    if texts.is_toast_shown() {
+        if !toast_timer.tick(time.delta()) {
+            return;
+        }
+        texts.despawn_toast(&mut commands);
+    }
+    let message = achievements.pop_toast()
+        .map(| achievement | format!(
+            "{}\n{}",
+            locale.format("achievement-unlocked", &[("name", achievement.get_name(&locale).into())]),
+            achievement.get_description(&locale)
+        ))
+        .or_else(| | {
+            let message = leaderboard?.pop_message(&locale)?;
+            Some(format!("{}\n{}", locale.get("leaderboard"), message))
+        });
+    if let Some(message) = message {
+        texts.spawn_toast(&mut commands, &locale, message);
+        toast_timer.start();
+    }
+}
+
+/// Sends ranked games to the leaderboard server, if `--leaderboard` was passed
+fn submit_to_leaderboard(
+    simulation: Res<Sim>,
+    leaderboard: Option<Res<LeaderboardClient>>,
+    mut game_over_events: EventReader<SimulationOver>,
+) {
+    let Some(leaderboard) = leaderboard else {
+        return;
+    };
+    if game_over_events.iter().next().is_some() && simulation.is_score_ranked() {
+        leaderboard.submit(&simulation);
+    }
+}
+
+/// Counts time and food while playing and adds every finished game to the stats
+fn update_stats(
+    time: Res<Time>,
+    game: Res<Game>,
+    simulation: Res<Sim>,
+    step_timers: Res<StepTimers>,
+    mut stats: ResMut<Stats>,
+    mut food_events: EventReader<FoodEaten>,
+    mut game_over_events: EventReader<SimulationOver>,
+) {
+    if matches!(game.state, GameState::SimulationRunning) {
+        stats.add_play_time(time.delta(), step_timers.is_boosting());
+    }
+    for _ in food_events.iter() {
+        stats.add_food(simulation.get_snake_length());
+    }
+    if let Some(event) = game_over_events.iter().next() {
+        stats.add_game(*event, &simulation);
+        if let Err(error) = stats.save() {
+            eprintln!("Couldn't save the stats: {}", error);
+        }
+    }
+}
+
+/// Steps the ghost along with the game and keeps finished games that beat it
+fn update_ghost(
+    simulation: Res<Sim>,
+    mut ghost: ResMut<Ghost>,
+    mut game_over_events: EventReader<SimulationOver>,
+) {
+    ghost.sync(&simulation);
+    if game_over_events.iter().next().is_some() {
+        ghost.record_finished_run(&simulation);
+    }
+}
+
+/// Shows how the game compares to the ghost, or to the opponent in versus, and how many are
+/// watching when streaming with `--spectate`
+fn update_split_text(
+    texts: Res<SnakeTexts>,
+    simulation: Res<Sim>,
+    ghost: Res<Ghost>,
+    locale: Res<Locale>,
+    versus: Option<Res<VersusMatch>>,
+    spectators: Option<Res<SpectatorServer>>,
+    mut text_query: Query<&mut Text>
+) {
+    let is_versus_changed = versus.as_ref().is_some_and(| versus | versus.is_changed());
+    let are_spectators_changed = spectators.as_ref().is_some_and(| spectators | spectators.is_changed());
+    if simulation.is_changed() || locale.is_changed() || is_versus_changed || are_spectators_changed {
+        if let Ok(mut text) = text_query.get_mut(texts.split) {
+            let split = match (&versus, ghost.get_split(&simulation)) {
+                (Some(versus), _) => Some(versus.get_status(&simulation, &locale)),
+                (None, Some(split)) => Some(locale.format("ghost-split", &[("split", format!("{:+}", split).into())])),
+                (None, None) => None
+            };
+            let viewers = spectators.map(| spectators | locale.format("spectate-viewers", &[("count", spectators.get_viewer_count().into())]));
+            let value = split.into_iter().chain(viewers).collect::<Vec<_>>().join("   ");
+            if text.sections[0].value != value {
+                text.sections[0].value = value;
             }
         }
     }
@@ -168,25 +947,29 @@ fn update_score(
 
 fn handle_food_eaten_event(
     bite_sound: Res<BiteSound>,
+    settings: Res<Settings>,
     mut commands: Commands,
     mut events: EventReader<FoodEaten>,
     mut step_timers: ResMut<StepTimers>,
 ) {
-    if let Some(event) = events.iter().next() {
-        if event.pieces_eaten % 5 == 0 {
-            step_timers.increase_tick_speed();
+    for event in events.iter() {
+        step_timers.on_food_eaten(event);
+        if settings.sound_enabled {
+            commands.spawn(AudioBundle {
+                source: bite_sound.0.clone(),
+                settings: PlaybackSettings::DESPAWN
+            });
         }
-        commands.spawn(AudioBundle {
-            source: bite_sound.0.clone(),
-            settings: PlaybackSettings::DESPAWN
-        });
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn handle_game_over_event(
     game_over_sound: Res<GameOverSound>,
     win_sound: Res<WinSound>,
-    asset_server: Res<AssetServer>,
+    locale: Res<Locale>,
+    settings: Res<Settings>,
+    simulation: Res<Sim>,
     mut commands: Commands,
     mut menu: ResMut<Game>,
     mut texts: ResMut<SnakeTexts>,
@@ -194,48 +977,389 @@ fn handle_game_over_event(
 ) {
     if let Some(event) = game_over_event.iter().next() {
         menu.state = GameState::GameOverMenu;
-        let sound_effect = if event.win {
+        let sound_effect = if event.reason.is_win() {
             win_sound.0.clone()
         } else {
             game_over_sound.0.clone()
         };
-        commands.spawn(AudioBundle {
-            source: sound_effect,
-            settings: PlaybackSettings::DESPAWN
-        });
-        texts.spawn_game_over_text(commands, asset_server, event.win);
+        if settings.sound_enabled {
+            commands.spawn(AudioBundle {
+                source: sound_effect,
+                settings: PlaybackSettings::DESPAWN
+            });
+        }
+        texts.spawn_game_over_text(&mut commands, &locale, *event, simulation.is_score_ranked());
+    }
+}
+
+/// Keeps the game for the next launch if the window is closed in the middle of it
+fn save_on_exit(
+    game: Res<Game>,
+    simulation: Res<Sim>,
+    step_timers: Res<StepTimers>,
+    player_input: Res<PlayerInput>,
+    daily: Res<DailySession>,
+    versus: Option<Res<VersusMatch>>,
+    exit_events: EventReader<AppExit>,
+) {
+    // A versus game can't be continued alone
+    let is_game_in_progress = versus.is_none() && matches!(
+        game.state,
+        GameState::SimulationRunning | GameState::Paused | GameState::ResumeCountdown
+    );
+    if !exit_events.is_empty() && is_game_in_progress {
+        save_game_in_progress(&simulation, &step_timers, &player_input, &daily);
+    }
+}
+
+/// The time played since the last finished game is only saved here
+fn save_stats_on_exit(stats: Res<Stats>, exit_events: EventReader<AppExit>) {
+    if !exit_events.is_empty() {
+        if let Err(error) = stats.save() {
+            eprintln!("Couldn't save the stats: {}", error);
+        }
+    }
+}
+
+/// Daily challenge attempts are never saved, they can only be played once
+fn save_game_in_progress(simulation: &Sim, step_timers: &StepTimers, player_input: &PlayerInput, daily: &DailySession) {
+    if simulation.is_game_running() && !daily.is_active() {
+        if let Err(error) = SavedGame::save(simulation, step_timers, player_input) {
+            eprintln!("Couldn't save the game: {}", error);
+        }
+    }
+}
+
+fn record_daily_result(
+    simulation: Res<Sim>,
+    mut daily: ResMut<DailySession>,
+    mut game_over_event: EventReader<SimulationOver>,
+) {
+    if let Some(event) = game_over_event.iter().next() {
+        daily.finish_attempt(&simulation, *event);
+    }
+}
+
+/// Streams the board and what happens on it to the viewers, if `--spectate` was passed
+fn update_spectators(
+    simulation: Res<Sim>,
+    spectators: Option<ResMut<SpectatorServer>>,
+    mut food_events: EventReader<FoodEaten>,
+    mut game_over_events: EventReader<SimulationOver>,
+) {
+    let Some(mut spectators) = spectators else {
+        return;
+    };
+    spectators.accept_viewers(&simulation);
+    for event in food_events.iter() {
+        spectators.send_food_eaten(event);
+    }
+    for event in game_over_events.iter() {
+        spectators.send_game_over(*event);
     }
+    spectators.send_board(&simulation);
+}
+
+/// The board is hidden in the level editor, which draws its own, and on the full screen menus
+fn is_board_shown(game: Res<Game>) -> bool {
+    !matches!(game.state, GameState::LevelEditor | GameState::Stats | GameState::Achievements)
 }
 
 impl Plugin for SnakePlugin {
     fn build(&self, app: &mut App) {
+        let mut simulation = Sim::new_simulation();
+        if self.level.is_some() {
+            simulation.set_level(self.level.clone());
+            simulation.reset_new_game();
+        }
+        if let Some(config) = &self.versus {
+            match VersusMatch::open(config) {
+                Ok(versus) => {
+                    app.insert_resource(versus);
+                },
+                Err(error) => eprintln!("Playing alone, couldn't open the versus connection: {}", error)
+            }
+        }
+        if let Some(port) = self.spectator_port {
+            match SpectatorServer::bind(port) {
+                Ok(spectators) => {
+                    app.insert_resource(spectators);
+                },
+                Err(error) => eprintln!("Not streaming, couldn't listen on port {}: {}", port, error)
+            }
+        }
+        if let Some(config) = &self.leaderboard {
+            app.insert_resource(LeaderboardClient::new(config.clone()));
+        }
         app
-            .insert_resource(Sim::new_simulation())
+            .insert_resource(simulation)
             .insert_resource(Game { state: GameState::StartMenu })
             .insert_resource(PlayerInput::default())
             .insert_resource(StepTimers::default())
             .insert_resource(SnakeTexts::default())
+            .insert_resource(PauseMenu::default())
+            .insert_resource(Settings { language: self.language, ..Default::default() })
+            .insert_resource(ResumeCountdown::default())
+            .insert_resource(DailySession::default())
+            .insert_resource(RewindBuffer::default())
+            .insert_resource(Ghost::default())
+            .insert_resource(Stats::load())
+            .insert_resource(AchievementTracker::load())
+            .insert_resource(ToastTimer::default())
+            .insert_resource(LevelEditor::new(self.editor_path.clone()))
             .add_event::<FoodEaten>()
             .add_event::<SimulationOver>()
+            .add_event::<SimulationTicked>()
             .add_systems(Startup, setup)
+            .insert_resource(FixedTime::new_from_secs(SIM_TIMESTEP_SECONDS as f32))
+            .add_systems(FixedUpdate, (update_simulation, update_versus))
+            .add_systems(Last, (save_on_exit, save_stats_on_exit))
             .add_systems(Update, (
-                update_simulation,
+                update_rewind,
                 update_start_menu,
                 update_pause_menu,
+                update_resume_countdown,
+                pause_on_focus_lost,
                 update_game_over_menu,
                 update_score,
+                update_effects_text,
+                update_timer_text,
                 handle_player_input,
                 handle_food_eaten_event,
                 handle_game_over_event,
-                render_game.after(update_simulation)
+                record_daily_result,
+                update_level_editor,
+                paint_level_editor,
+                update_editor_text.after(update_level_editor).after(paint_level_editor),
+                render_game.after(update_ghost).run_if(is_board_shown),
+                render_level_editor.run_if(| game: Res<Game> | matches!(game.state, GameState::LevelEditor))
+            ))
+            .add_systems(Update, (
+                update_ghost,
+                update_split_text.after(update_ghost),
+                update_stats,
+                update_stats_screen,
+                update_speed_text,
+                update_achievements,
+                update_toasts,
+                update_achievements_screen,
+                update_language.after(update_pause_menu),
+                update_versus_lobby,
+                update_spectators,
+                submit_to_leaderboard,
+                render_stats.run_if(| game: Res<Game> | matches!(game.state, GameState::Stats)),
+                render_stamina.run_if(is_board_shown),
+                render_combo.run_if(is_board_shown)
             )
         );
     }
 }
 
+fn get_arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+    args.iter().position(| arg | arg == name).and_then(| index | args.get(index + 1))
+}
+
+/// Reads the level passed as `--level <path>`, if any
+fn get_level_from_args(args: &[String]) -> Option<Level> {
+    let path = get_arg_value(args, "--level")?;
+    match Level::load(std::path::Path::new(path)) {
+        Ok(level) => Some(level),
+        Err(error) => {
+            eprintln!("Ignoring level {}: {}", path, error);
+            None
+        }
+    }
+}
+
+/// Reads `--host <port>` or `--join <address>`, along with `--input-delay <ticks>` and the loopback
+/// testing options `--net-latency <milliseconds>` and `--net-loss <percent>`
+fn get_versus_config_from_args(args: &[String]) -> Option<VersusConfig> {
+    let role = match get_arg_value(args, "--host") {
+        Some(port) => match port.parse() {
+            Ok(port) => VersusRole::Host { port },
+            Err(_) => {
+                eprintln!("Ignoring --host, {} is not a port", port);
+                return None;
+            }
+        },
+        None => VersusRole::Join { address: get_arg_value(args, "--join")?.clone() }
+    };
+    let parse_or = | name: &str, default: u64 | get_arg_value(args, name).and_then(| value | value.parse().ok()).unwrap_or(default);
+    Some(VersusConfig {
+        role,
+        input_delay: parse_or("--input-delay", DEFAULT_INPUT_DELAY_TICKS as u64) as Sze,
+        latency: Duration::from_millis(parse_or("--net-latency", 0)),
+        loss_percent: parse_or("--net-loss", 0).min(100) as u32
+    })
+}
+
+/// Reads `--spectate [port]`, the port is optional so it can't be taken from the next option
+fn get_spectator_port_from_args(args: &[String]) -> Option<u16> {
+    args.iter().position(| arg | arg == "--spectate")?;
+    Some(get_arg_value(args, "--spectate").and_then(| port | port.parse().ok()).unwrap_or(DEFAULT_SPECTATOR_PORT))
+}
+
+/// Handles `--headless <best run file> [--spectate <port>]`, which streams the recorded game over and
+/// over without opening a window and never returns unless the file can't be served
+fn serve_headless_from_args(args: &[String]) -> bool {
+    let Some(path) = get_arg_value(args, "--headless") else {
+        return false;
+    };
+    let best_run = match BestRun::load_from(std::path::Path::new(path)) {
+        Ok(best_run) => best_run,
+        Err(error) => {
+            eprintln!("Couldn't load the run to stream from {}: {}", path, error);
+            return true;
+        }
+    };
+    let port = get_spectator_port_from_args(args).unwrap_or(DEFAULT_SPECTATOR_PORT);
+    match SpectatorServer::bind(port) {
+        Ok(mut spectators) => {
+            println!("Streaming {} on port {}", path, port);
+            spectate::serve_replay(&mut spectators, &best_run.replay)
+        },
+        Err(error) => eprintln!("Couldn't listen on port {}: {}", port, error)
+    }
+    true
+}
+
+/// Reads `--leaderboard <address>` and `--player <name>`, the name defaults to the user's
+fn get_leaderboard_config_from_args(args: &[String]) -> Option<LeaderboardConfig> {
+    let address = get_arg_value(args, "--leaderboard")?.clone();
+    let player = get_arg_value(args, "--player")
+        .cloned()
+        .or_else(| | std::env::var("USER").or_else(| _ | std::env::var("USERNAME")).ok())
+        .unwrap_or_else(| | "Player".to_string());
+    Some(LeaderboardConfig { address, player })
+}
+
+/// Reads `--language <code>`, like `de`, or falls back to the language of the system
+fn get_language_from_args(args: &[String]) -> &'static Language {
+    let Some(code) = get_arg_value(args, "--language") else {
+        return get_system_language();
+    };
+    find_language(code).unwrap_or_else(| | {
+        eprintln!("No {} translation, using the system language", code);
+        get_system_language()
+    })
+}
+
+/// Handles `--leaderboard-server [port] [--leaderboard-file <path>]`, which keeps the boards and
+/// checks the submitted games without opening a window
+fn serve_leaderboard_from_args(args: &[String]) -> bool {
+    if !args.iter().any(| arg | arg == "--leaderboard-server") {
+        return false;
+    }
+    let port = get_arg_value(args, "--leaderboard-server").and_then(| port | port.parse().ok()).unwrap_or(DEFAULT_LEADERBOARD_PORT);
+    let path = get_arg_value(args, "--leaderboard-file").map_or(DEFAULT_LEADERBOARD_PATH, | path | path.as_str());
+    println!("Serving the leaderboard in {} on port {}", path, port);
+    if let Err(error) = leaderboard::serve(port, std::path::Path::new(path)) {
+        eprintln!("Couldn't serve the leaderboard: {}", error);
+    }
+    true
+}
+
+/// Handles `--leaderboard <address> --top <mode>/<difficulty> [--offset <n>] [--limit <n>]`, which
+/// prints a page of a board
+///
+/// Level boards also take the level, as its hash or as `--level <path>`.
+fn print_leaderboard_from_args(args: &[String]) -> bool {
+    let Some(board_path) = get_arg_value(args, "--top") else {
+        return false;
+    };
+    let board_path = match get_level_from_args(args) {
+        Some(level) => format!("{}/{:016x}", board_path, level.get_hash()),
+        None => board_path.clone()
+    };
+    let Some(board) = leaderboard::parse_board_path(&board_path) else {
+        eprintln!("No board {}, boards are written like classic/normal or time-attack/hard, level boards need --level", board_path);
+        return true;
+    };
+    let Some(address) = get_arg_value(args, "--leaderboard") else {
+        eprintln!("--top needs the --leaderboard <address> to ask");
+        return true;
+    };
+    let parse_or = | name: &str, default: usize | get_arg_value(args, name).and_then(| value | value.parse().ok()).unwrap_or(default);
+    match leaderboard::get_page(address, board, parse_or("--offset", 0), parse_or("--limit", DEFAULT_PAGE_SIZE)) {
+        Ok(page) => {
+            let locale = Locale::without_font(get_language_from_args(args));
+            println!("{}", locale.format("leaderboard-top", &[
+                ("mode", locale.get(board.mode.get_message_id()).into()),
+                ("difficulty", locale.get(board.difficulty.get_message_id()).into()),
+                ("total", page.total.into())
+            ]));
+            for (index, entry) in page.entries.iter().enumerate() {
+                println!("{:>5}. {:<16} {:>8}", page.offset + index + 1, entry.player, entry.score);
+            }
+        },
+        Err(error) => eprintln!("Couldn't get the board from {}: {}", address, error)
+    }
+    true
+}
+
+/// Handles `--watch <address>`, which opens a window showing a game streamed with `--spectate`
+fn watch_from_args(args: &[String]) -> bool {
+    let Some(address) = get_arg_value(args, "--watch") else {
+        return false;
+    };
+    match SpectatorClient::connect(address) {
+        Ok(client) => {
+            App::new()
+                .add_plugins((DefaultPlugins, ViewerPlugin { language: get_language_from_args(args) }))
+                .insert_resource(client)
+                .add_systems(Update, bevy::window::close_on_esc)
+                .run();
+        },
+        Err(error) => eprintln!("Couldn't watch {}: {}", address, error)
+    }
+    true
+}
+
+/// Handles `--generate <dir> [--seed <seed>] [--difficulty <0-10>] [--count <levels>]`
+///
+/// Writes `count` levels for consecutive seeds starting at `seed` and returns true if the game
+/// shouldn't start afterwards.
+fn generate_levels_from_args(args: &[String]) -> bool {
+    let Some(dir) = get_arg_value(args, "--generate") else {
+        return false;
+    };
+    let parse_or = | name: &str, default: u64 | get_arg_value(args, name).and_then(| value | value.parse().ok()).unwrap_or(default);
+    let first_seed = parse_or("--seed", rand::random());
+    let difficulty = parse_or("--difficulty", 5).min(generator::MAX_DIFFICULTY as u64) as u32;
+    let count = parse_or("--count", 1);
+    if let Err(error) = std::fs::create_dir_all(dir) {
+        eprintln!("Couldn't create {}: {}", dir, error);
+        return true;
+    }
+    for seed in first_seed..first_seed.saturating_add(count) {
+        let path = std::path::Path::new(dir).join(format!("generated-{}-d{}.txt", seed, difficulty));
+        match generator::generate_level(seed, difficulty).save(&path) {
+            Ok(()) => println!("{}", path.display()),
+            Err(error) => eprintln!("Couldn't write {}: {}", path.display(), error)
+        }
+    }
+    true
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if generate_levels_from_args(&args)
+        || serve_headless_from_args(&args)
+        || watch_from_args(&args)
+        || serve_leaderboard_from_args(&args)
+        || print_leaderboard_from_args(&args)
+    {
+        return;
+    }
     App::new()
-        .add_plugins((DefaultPlugins, SnakePlugin))
+        .add_plugins((DefaultPlugins, SnakePlugin {
+            level: get_level_from_args(&args),
+            editor_path: get_arg_value(&args, "--edit").map_or(DEFAULT_EDITOR_LEVEL_PATH.into(), | path | path.into()),
+            versus: get_versus_config_from_args(&args),
+            spectator_port: get_spectator_port_from_args(&args),
+            leaderboard: get_leaderboard_config_from_args(&args),
+            language: get_language_from_args(&args)
+        }))
         .add_systems(Update, bevy::window::close_on_esc)
         .run();
 }
@@ -0,0 +1,189 @@
+use bevy::prelude::Resource;
+
+use crate::{settings::Settings, input::{get_controls_description, REWIND_KEY}, locale::Locale, rules::TimeAttackRules};
+
+const PAUSE_OPTIONS: [PauseOption; 6] = [
+    PauseOption::Resume,
+    PauseOption::Restart,
+    PauseOption::SaveGame,
+    PauseOption::Settings,
+    PauseOption::Controls,
+    PauseOption::QuitToMainMenu
+];
+
+const SETTINGS_OPTIONS: [SettingsOption; 9] = [
+    SettingsOption::Sound,
+    SettingsOption::Difficulty,
+    SettingsOption::FoodCount,
+    SettingsOption::PortalPairs,
+    SettingsOption::Hazards,
+    SettingsOption::TimeAttack,
+    SettingsOption::PracticeMode,
+    SettingsOption::Language,
+    SettingsOption::Back
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PauseOption { Resume, Restart, SaveGame, Settings, Controls, QuitToMainMenu }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SettingsOption { Sound, Difficulty, FoodCount, PortalPairs, Hazards, TimeAttack, PracticeMode, Language, Back }
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum PausePage { #[default] Main, Settings, Controls }
+
+/// What the game should do after the player confirms a pause menu entry
+#[derive(Debug, PartialEq)]
+pub enum PauseAction { Resume, Restart, SaveGame, QuitToMainMenu, Redraw }
+
+#[derive(Resource, Default)]
+pub struct PauseMenu {
+    page: PausePage,
+    selected: usize,
+    /// Shown under the title until the menu is closed, like the result of saving
+    message: Option<String>
+}
+
+impl PauseOption {
+    pub fn get_message_id(&self) -> &'static str {
+        match self {
+            Self::Resume => "pause-resume",
+            Self::Restart => "pause-restart",
+            Self::SaveGame => "pause-save-game",
+            Self::Settings => "pause-settings",
+            Self::Controls => "pause-controls",
+            Self::QuitToMainMenu => "pause-quit"
+        }
+    }
+}
+
+impl PauseMenu {
+    pub fn open(&mut self) {
+        self.page = PausePage::Main;
+        self.selected = 0;
+        self.message = None;
+    }
+
+    pub fn set_message(&mut self, message: String) { self.message = Some(message); }
+
+    pub fn get_title(&self, locale: &Locale) -> String {
+        match self.page {
+            PausePage::Main => match &self.message {
+                Some(message) => format!("{}\n{}\n", locale.get("pause-title"), message),
+                None => format!("{}\n", locale.get("pause-title"))
+            },
+            PausePage::Settings => format!("{}\n", locale.get("settings-title")),
+            PausePage::Controls => format!("{}\n{}\n", locale.get("controls-title"), get_controls_description(locale))
+        }
+    }
+
+    pub fn get_entries(&self, settings: &Settings, locale: &Locale) -> Vec<String> {
+        let on_off = | enabled: bool | locale.get(if enabled { "setting-on" } else { "setting-off" });
+        match self.page {
+            PausePage::Main => PAUSE_OPTIONS.iter().map(| option | locale.get(option.get_message_id())).collect(),
+            PausePage::Settings => SETTINGS_OPTIONS.iter().map(| option | {
+                match option {
+                    SettingsOption::Sound => locale.format("setting-sound", &[("state", on_off(settings.sound_enabled).into())]),
+                    SettingsOption::Difficulty => locale.format(
+                        "setting-difficulty", &[("difficulty", locale.get(settings.difficulty.get_message_id()).into())]
+                    ),
+                    SettingsOption::FoodCount => locale.format("setting-food-count", &[("count", settings.food_count.into())]),
+                    SettingsOption::PortalPairs => locale.format("setting-portals", &[("count", settings.portal_pairs.into())]),
+                    SettingsOption::Hazards => locale.format("setting-hazards", &[("state", on_off(settings.hazards_enabled).into())]),
+                    SettingsOption::TimeAttack => {
+                        let mode = if settings.time_attack_enabled {
+                            let time_attack = TimeAttackRules::default();
+                            locale.format("mode-time-attack-budget", &[
+                                ("ticks", time_attack.budget_ticks.into()),
+                                ("bonus", time_attack.bonus_ticks_per_food.into())
+                            ])
+                        } else {
+                            locale.get("mode-classic")
+                        };
+                        locale.format("setting-mode", &[("mode", mode.into())])
+                    },
+                    SettingsOption::PracticeMode => locale.format(
+                        "setting-practice", &[("key", REWIND_KEY.str.into()), ("state", on_off(settings.practice_mode).into())]
+                    ),
+                    SettingsOption::Language => locale.format("setting-language", &[("language", locale.get("language-name").into())]),
+                    SettingsOption::Back => locale.get("menu-back")
+                }
+            }).collect(),
+            PausePage::Controls => vec!(locale.get("menu-back"))
+        }
+    }
+
+    pub fn get_selected(&self) -> usize { self.selected }
+
+    pub fn select_previous(&mut self) {
+        let entries_len = self.get_entries_len();
+        self.selected = (self.selected + entries_len - 1) % entries_len;
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.get_entries_len();
+    }
+
+    pub fn confirm(&mut self, settings: &mut Settings) -> PauseAction {
+        match self.page {
+            PausePage::Main => match PAUSE_OPTIONS[self.selected] {
+                PauseOption::Resume => PauseAction::Resume,
+                PauseOption::Restart => PauseAction::Restart,
+                PauseOption::SaveGame => PauseAction::SaveGame,
+                PauseOption::QuitToMainMenu => PauseAction::QuitToMainMenu,
+                PauseOption::Settings => self.go_to_page(PausePage::Settings),
+                PauseOption::Controls => self.go_to_page(PausePage::Controls)
+            },
+            PausePage::Settings => match SETTINGS_OPTIONS[self.selected] {
+                SettingsOption::Sound => {
+                    settings.toggle_sound();
+                    PauseAction::Redraw
+                },
+                SettingsOption::Difficulty => {
+                    settings.cycle_difficulty();
+                    PauseAction::Redraw
+                },
+                SettingsOption::FoodCount => {
+                    settings.cycle_food_count();
+                    PauseAction::Redraw
+                },
+                SettingsOption::PortalPairs => {
+                    settings.cycle_portal_pairs();
+                    PauseAction::Redraw
+                },
+                SettingsOption::Hazards => {
+                    settings.toggle_hazards();
+                    PauseAction::Redraw
+                },
+                SettingsOption::TimeAttack => {
+                    settings.toggle_time_attack();
+                    PauseAction::Redraw
+                },
+                SettingsOption::PracticeMode => {
+                    settings.toggle_practice_mode();
+                    PauseAction::Redraw
+                },
+                SettingsOption::Language => {
+                    settings.cycle_language();
+                    PauseAction::Redraw
+                },
+                SettingsOption::Back => self.go_to_page(PausePage::Main)
+            },
+            PausePage::Controls => self.go_to_page(PausePage::Main)
+        }
+    }
+
+    fn go_to_page(&mut self, page: PausePage) -> PauseAction {
+        self.page = page;
+        self.selected = 0;
+        PauseAction::Redraw
+    }
+
+    fn get_entries_len(&self) -> usize {
+        match self.page {
+            PausePage::Main => PAUSE_OPTIONS.len(),
+            PausePage::Settings => SETTINGS_OPTIONS.len(),
+            PausePage::Controls => 1
+        }
+    }
+}
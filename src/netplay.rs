@@ -0,0 +1,597 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant}
+};
+use bevy::prelude::Resource;
+use rand::Rng;
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    Sze,
+    cell::CellPos,
+    input::DirectionQueue,
+    locale::Locale,
+    rules::GameRules,
+    simulation::Sim,
+    timers::SpeedState,
+    utils::Direction
+};
+
+/// Ticks between a key press and the move it makes, raise it on slow connections as a late input
+/// holds up both games until it arrives
+pub const DEFAULT_INPUT_DELAY_TICKS: Sze = 1;
+/// Unconfirmed inputs are sent again with every packet, up to this many
+const MAX_INPUTS_PER_PACKET: usize = 64;
+const HASH_INTERVAL_TICKS: Sze = 10;
+/// The latest hashes go out with every packet, so a lost packet doesn't skip a check
+const HASHES_PER_PACKET: usize = 4;
+const MAX_KEPT_HASHES: usize = 64;
+const DISCONNECT_SECONDS: u64 = 5;
+/// Steps held back while waiting for the peer, more would make the game rush after a stall
+const MAX_DUE_STEPS: u32 = 2;
+/// The largest UDP payload
+const MAX_PACKET_BYTES: usize = 65507;
+
+/// How this side takes part in a versus match, read from the command line
+#[derive(Clone, Debug)]
+pub enum VersusRole {
+    /// Waits for a guest on `port` and picks the seed and rules
+    Host { port: u16 },
+    Join { address: String }
+}
+
+#[derive(Clone, Debug)]
+pub struct VersusConfig {
+    pub role: VersusRole,
+    pub input_delay: Sze,
+    /// Added to every packet sent, to try out a bad connection over loopback
+    pub latency: Duration,
+    pub loss_percent: u32
+}
+
+/// Sends and receives whole packets without blocking, packets may get lost or come out of order
+pub trait Transport: Send + Sync {
+    fn send(&mut self, bytes: &[u8]);
+    fn recv(&mut self) -> Option<Vec<u8>>;
+}
+
+/// A non-blocking UDP socket that talks to a single peer
+pub struct UdpTransport {
+    socket: UdpSocket,
+    /// The host learns it from the first packet that comes in
+    peer: Option<SocketAddr>,
+    buffer: Vec<u8>
+}
+
+/// Drops and delays the packets sent through `inner`, so a bad connection can be played over loopback
+pub struct LossyTransport {
+    inner: Box<dyn Transport>,
+    latency: Duration,
+    loss_percent: u32,
+    delayed: VecDeque<(Instant, Vec<u8>)>
+}
+
+#[derive(Serialize, Deserialize)]
+enum Packet {
+    /// Sent by the guest until the match starts
+    Join,
+    Start { seed: u64, rules: Box<GameRules>, input_delay: Sze },
+    /// The moves from `first_tick` on that the peer hasn't confirmed yet, `received` confirms the
+    /// peer's moves in turn
+    Inputs { first_tick: Sze, directions: Vec<Option<Direction>>, received: Sze, hashes: Vec<(Sze, u64)> }
+}
+
+/// Keeps the moves of both players in step over a `Transport`
+///
+/// Only the seed, the rules and one move per player and tick go over the wire, both sides run the
+/// same simulations from them. Moves are sent again until the peer confirms them, so a lost packet
+/// is made up for by the next one.
+pub struct LockstepSession {
+    transport: Box<dyn Transport>,
+    is_host: bool,
+    /// Seed and rules of the match, `None` until the host started it
+    match_start: Option<(u64, GameRules)>,
+    input_delay: Sze,
+    /// Moves by tick, starting with `input_delay` empty ones on both sides
+    local_inputs: Vec<Option<Direction>>,
+    remote_inputs: Vec<Option<Direction>>,
+    /// Local moves the peer confirmed, the rest is sent again
+    acked_inputs: usize,
+    local_hashes: BTreeMap<Sze, u64>,
+    /// Peer hashes of ticks this side hasn't reached yet
+    remote_hashes: BTreeMap<Sze, u64>,
+    desync_tick: Option<Sze>,
+    last_received: Option<Instant>
+}
+
+/// A versus game against a peer, the opponent's game runs in its own `Sim` next to the local one
+#[derive(Resource)]
+pub struct VersusMatch {
+    session: LockstepSession,
+    opponent: Option<Sim>,
+    next_tick: Sze,
+    due_steps: u32
+}
+
+impl UdpTransport {
+    /// For the host, the guest is whoever sends the first packet
+    pub fn bind(port: u16) -> io::Result<Self> {
+        Self::open((Ipv4Addr::UNSPECIFIED, port).into(), None)
+    }
+
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self> {
+        let peer = address.to_socket_addrs()?
+            .next()
+            .ok_or_else(| | io::Error::new(io::ErrorKind::NotFound, "the address didn't resolve"))?;
+        let local_address: SocketAddr = if peer.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        Self::open(local_address, Some(peer))
+    }
+
+    pub fn get_local_port(&self) -> io::Result<u16> { Ok(self.socket.local_addr()?.port()) }
+
+    fn open(local_address: SocketAddr, peer: Option<SocketAddr>) -> io::Result<Self> {
+        let socket = UdpSocket::bind(local_address)?;
+        socket.set_nonblocking(true)?;
+        Ok(UdpTransport { socket, peer, buffer: vec!(0; MAX_PACKET_BYTES) })
+    }
+}
+
+impl Transport for UdpTransport {
+    /// A failed send counts as a lost packet, the moves in it are sent again anyway
+    fn send(&mut self, bytes: &[u8]) {
+        if let Some(peer) = self.peer {
+            let _ = self.socket.send_to(bytes, peer);
+        }
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        loop {
+            match self.socket.recv_from(&mut self.buffer) {
+                Ok((length, from)) => {
+                    if *self.peer.get_or_insert(from) == from {
+                        return Some(self.buffer[..length].to_vec());
+                    }
+                },
+                // Some platforms report an earlier send to a peer that wasn't up yet here
+                Err(error) if error.kind() == io::ErrorKind::ConnectionReset => {},
+                Err(_) => return None
+            }
+        }
+    }
+}
+
+impl LossyTransport {
+    pub fn new(inner: Box<dyn Transport>, latency: Duration, loss_percent: u32) -> Self {
+        LossyTransport { inner, latency, loss_percent, delayed: VecDeque::new() }
+    }
+
+    fn send_due(&mut self) {
+        while self.delayed.front().is_some_and(| (due, _) | *due <= Instant::now()) {
+            if let Some((_, bytes)) = self.delayed.pop_front() {
+                self.inner.send(&bytes);
+            }
+        }
+    }
+}
+
+impl Transport for LossyTransport {
+    fn send(&mut self, bytes: &[u8]) {
+        if rand::thread_rng().gen_range(0..100) >= self.loss_percent {
+            self.delayed.push_back((Instant::now() + self.latency, bytes.to_vec()));
+        }
+        self.send_due();
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        self.send_due();
+        self.inner.recv()
+    }
+}
+
+impl LockstepSession {
+    pub fn new(transport: Box<dyn Transport>, is_host: bool, input_delay: Sze) -> Self {
+        LockstepSession {
+            transport,
+            is_host,
+            match_start: None,
+            input_delay,
+            local_inputs: vec!(),
+            remote_inputs: vec!(),
+            acked_inputs: 0,
+            local_hashes: BTreeMap::new(),
+            remote_hashes: BTreeMap::new(),
+            desync_tick: None,
+            last_received: None
+        }
+    }
+
+    pub fn is_host(&self) -> bool { self.is_host }
+
+    pub fn has_peer(&self) -> bool { self.last_received.is_some() }
+
+    pub fn get_match_start(&self) -> Option<&(u64, GameRules)> { self.match_start.as_ref() }
+
+    /// Only the host starts the match, the guest takes the seed and rules from it
+    pub fn start_match(&mut self, seed: u64, rules: GameRules) {
+        if self.is_host && self.match_start.is_none() {
+            self.begin(seed, rules, self.input_delay);
+            self.send_start();
+        }
+    }
+
+    /// Handles every packet that came in since the last call
+    pub fn poll(&mut self) {
+        while let Some(bytes) = self.transport.recv() {
+            let Some(packet) = std::str::from_utf8(&bytes).ok().and_then(| text | ron::from_str(text).ok()) else {
+                continue;
+            };
+            self.last_received = Some(Instant::now());
+            match packet {
+                Packet::Join => {
+                    if self.is_host {
+                        self.send_start();
+                    }
+                },
+                Packet::Start { seed, rules, input_delay } => {
+                    if !self.is_host && self.match_start.is_none() {
+                        self.begin(seed, *rules, input_delay);
+                    }
+                },
+                Packet::Inputs { first_tick, directions, received, hashes } => {
+                    if self.match_start.is_some() {
+                        self.receive_inputs(first_tick as usize, &directions);
+                        self.acked_inputs = self.acked_inputs.max(received as usize).min(self.local_inputs.len());
+                        for (tick, hash) in hashes {
+                            self.receive_hash(tick, hash);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends the moves the peer hasn't confirmed, the guest asks to join instead until the match starts
+    pub fn flush(&mut self) {
+        let packet = match self.match_start {
+            None if self.is_host => return,
+            None => Packet::Join,
+            Some(_) => Packet::Inputs {
+                first_tick: self.acked_inputs as Sze,
+                directions: self.local_inputs[self.acked_inputs..].iter().take(MAX_INPUTS_PER_PACKET).copied().collect(),
+                received: self.remote_inputs.len() as Sze,
+                hashes: self.local_hashes.iter().rev().take(HASHES_PER_PACKET).map(| (&tick, &hash) | (tick, hash)).collect()
+            }
+        };
+        self.send(&packet);
+    }
+
+    /// Takes the next move from `input_direction` once per tick, to be made `input_delay` ticks later
+    pub fn add_local_input(&mut self, tick: Sze, input_direction: &mut DirectionQueue) {
+        if self.match_start.is_some() && self.local_inputs.len() <= (tick + self.input_delay) as usize {
+            self.local_inputs.push(input_direction.pop());
+        }
+    }
+
+    /// The local and the remote move of `tick`, `None` while either is missing
+    pub fn get_inputs(&self, tick: Sze) -> Option<(Option<Direction>, Option<Direction>)> {
+        Some((*self.local_inputs.get(tick as usize)?, *self.remote_inputs.get(tick as usize)?))
+    }
+
+    pub fn record_hash(&mut self, tick: Sze, hash: u64) {
+        if let Some(remote_hash) = self.remote_hashes.remove(&tick) {
+            self.compare_hashes(tick, hash, remote_hash);
+        }
+        self.local_hashes.insert(tick, hash);
+        while self.local_hashes.len() > MAX_KEPT_HASHES {
+            self.local_hashes.pop_first();
+        }
+    }
+
+    /// The first tick the peers' games were found to differ at
+    pub fn get_desync_tick(&self) -> Option<Sze> { self.desync_tick }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.match_start.is_some()
+            && self.last_received.is_some_and(| last_received | last_received.elapsed() > Duration::from_secs(DISCONNECT_SECONDS))
+    }
+
+    fn begin(&mut self, seed: u64, rules: GameRules, input_delay: Sze) {
+        self.match_start = Some((seed, rules));
+        self.input_delay = input_delay;
+        self.local_inputs = vec!(None; input_delay as usize);
+        self.remote_inputs = vec!(None; input_delay as usize);
+    }
+
+    fn send_start(&mut self) {
+        if let Some((seed, rules)) = self.match_start.clone() {
+            self.send(&Packet::Start { seed, rules: Box::new(rules), input_delay: self.input_delay });
+        }
+    }
+
+    fn send(&mut self, packet: &Packet) {
+        match ron::to_string(packet) {
+            Ok(contents) => self.transport.send(contents.as_bytes()),
+            Err(error) => eprintln!("Couldn't encode a versus packet: {}", error)
+        }
+    }
+
+    /// Moves the peer sent again are skipped, a gap means an older packet is still missing
+    fn receive_inputs(&mut self, first_tick: usize, directions: &[Option<Direction>]) {
+        for (tick, &direction) in (first_tick..).zip(directions) {
+            if tick == self.remote_inputs.len() {
+                self.remote_inputs.push(direction);
+            } else if tick > self.remote_inputs.len() {
+                break;
+            }
+        }
+    }
+
+    fn receive_hash(&mut self, tick: Sze, hash: u64) {
+        match self.local_hashes.get(&tick) {
+            Some(&local_hash) => self.compare_hashes(tick, local_hash, hash),
+            None if self.local_hashes.last_key_value().map_or(true, | (&last_tick, _) | tick > last_tick) => {
+                self.remote_hashes.insert(tick, hash);
+                while self.remote_hashes.len() > MAX_KEPT_HASHES {
+                    self.remote_hashes.pop_first();
+                }
+            },
+            // Already dropped locally
+            None => {}
+        }
+    }
+
+    fn compare_hashes(&mut self, tick: Sze, local_hash: u64, remote_hash: u64) {
+        if local_hash != remote_hash && self.desync_tick.map_or(true, | desync_tick | tick < desync_tick) {
+            self.desync_tick = Some(tick);
+        }
+    }
+}
+
+impl VersusMatch {
+    pub fn new(session: LockstepSession) -> Self {
+        VersusMatch { session, opponent: None, next_tick: 0, due_steps: 0 }
+    }
+
+    pub fn open(config: &VersusConfig) -> io::Result<Self> {
+        let (transport, is_host): (Box<dyn Transport>, bool) = match &config.role {
+            VersusRole::Host { port } => (Box::new(UdpTransport::bind(*port)?), true),
+            VersusRole::Join { address } => (Box::new(UdpTransport::connect(address.as_str())?), false)
+        };
+        let transport: Box<dyn Transport> = if config.latency.is_zero() && config.loss_percent == 0 {
+            transport
+        } else {
+            Box::new(LossyTransport::new(transport, config.latency, config.loss_percent))
+        };
+        Ok(Self::new(LockstepSession::new(transport, is_host, config.input_delay)))
+    }
+
+    pub fn is_started(&self) -> bool { self.opponent.is_some() }
+
+    /// Resets both games to the seed and rules of the match once it is on, the host starts it as
+    /// soon as a guest joined and plays with its own rules
+    pub fn try_start(&mut self, sim: &mut Sim) -> bool {
+        self.session.poll();
+        if self.session.is_host() && self.session.has_peer() {
+            self.session.start_match(rand::random(), sim.get_rules().clone());
+        }
+        self.session.flush();
+        if self.is_started() {
+            return true;
+        }
+        let Some((seed, rules)) = self.session.get_match_start().cloned() else {
+            return false;
+        };
+        sim.set_rules(rules.clone());
+        sim.reset_with_seed(seed);
+        let mut opponent = Sim::new_simulation();
+        opponent.set_rules(rules);
+        opponent.reset_with_seed(seed);
+        self.opponent = Some(opponent);
+        true
+    }
+
+    pub fn poll(&mut self) { self.session.poll(); }
+
+    pub fn flush(&mut self) { self.session.flush(); }
+
+    /// Runs the steps the timers asked for as far as the peer's moves allow, `run_step` takes the
+    /// local ones so they send their events
+    pub fn run_due_steps(
+        &mut self,
+        new_steps: u32,
+        sim: &mut Sim,
+        input_direction: &mut DirectionQueue,
+        mut run_step: impl FnMut(&mut Sim, &mut DirectionQueue)
+    ) {
+        self.due_steps = (self.due_steps + new_steps).min(MAX_DUE_STEPS);
+        while self.due_steps > 0 && self.try_step(sim, input_direction, &mut run_step) {
+            self.due_steps -= 1;
+        }
+    }
+
+    /// The speed both sides play at, the level the player further along the difficulty's curve earned
+    ///
+    /// It only follows what both sides know, so their games keep the same pace. Speed and slow food
+    /// don't change it.
+    pub fn get_speed(&self, sim: &Sim) -> SpeedState {
+        let preset = sim.get_difficulty().get_preset();
+        let earned_levels = [Some(sim), self.opponent.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(| player_sim | preset.get_earned_levels(player_sim.get_eaten_food(), player_sim.get_score()))
+            .max()
+            .unwrap_or(0);
+        SpeedState { level: earned_levels, earned_levels }
+    }
+
+    pub fn is_over(&self, sim: &Sim) -> bool {
+        self.session.get_desync_tick().is_some()
+            || self.session.is_disconnected()
+            || self.opponent.as_ref().is_some_and(| opponent | !opponent.is_game_running() && !sim.is_game_running())
+    }
+
+    pub fn get_opponent(&self) -> Option<&Sim> { self.opponent.as_ref() }
+
+    pub fn get_opponent_snake_cells(&self) -> Vec<CellPos> {
+        self.opponent.iter().flat_map(| opponent | opponent.get_snake_cells()).collect()
+    }
+
+    pub fn get_status(&self, sim: &Sim, locale: &Locale) -> String {
+        let Some(opponent) = &self.opponent else {
+            return locale.get(if self.session.is_host() { "versus-waiting" } else { "versus-joining" });
+        };
+        if let Some(tick) = self.session.get_desync_tick() {
+            return locale.format("versus-out-of-sync", &[("tick", tick.into())]);
+        }
+        if self.session.is_disconnected() {
+            return locale.get("versus-disconnected");
+        }
+        let split = sim.get_score() as i64 - opponent.get_score() as i64;
+        if !self.is_over(sim) {
+            return locale.format("versus-opponent", &[
+                ("score", opponent.get_score().into()),
+                ("split", format!("{:+}", split).into())
+            ]);
+        }
+        match split {
+            0 => locale.get("versus-draw"),
+            split if split > 0 => locale.format("versus-won", &[("points", split.into())]),
+            split => locale.format("versus-lost", &[("points", (-split).into())])
+        }
+    }
+
+    /// Steps both games once the moves of both players for the next tick are in
+    fn try_step(
+        &mut self,
+        sim: &mut Sim,
+        input_direction: &mut DirectionQueue,
+        run_step: &mut impl FnMut(&mut Sim, &mut DirectionQueue)
+    ) -> bool {
+        let Some(opponent) = &mut self.opponent else {
+            return false;
+        };
+        if self.session.get_desync_tick().is_some() || self.session.is_disconnected() {
+            return false;
+        }
+        self.session.add_local_input(self.next_tick, input_direction);
+        let Some((local_direction, remote_direction)) = self.session.get_inputs(self.next_tick) else {
+            return false;
+        };
+        if sim.is_game_running() {
+            run_step(sim, &mut get_direction_queue(local_direction));
+        }
+        if opponent.is_game_running() {
+            opponent.step(&mut get_direction_queue(remote_direction));
+        }
+        self.next_tick += 1;
+        if self.next_tick % HASH_INTERVAL_TICKS == 0 {
+            let (host_sim, guest_sim) = if self.session.is_host() { (&*sim, &*opponent) } else { (&*opponent, &*sim) };
+            self.session.record_hash(self.next_tick, host_sim.get_grid_hash() ^ guest_sim.get_grid_hash().rotate_left(32));
+        }
+        true
+    }
+}
+
+fn get_direction_queue(direction: Option<Direction>) -> DirectionQueue {
+    let mut input_direction = DirectionQueue::default();
+    if let Some(direction) = direction {
+        input_direction.push(direction);
+    }
+    input_direction
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+    use super::*;
+
+    const TEST_TICKS: Sze = 300;
+
+    fn open_lossy_pair() -> (VersusMatch, VersusMatch) {
+        let latency = Duration::from_millis(50);
+        let host_transport = UdpTransport::bind(0).unwrap();
+        let port = host_transport.get_local_port().unwrap();
+        let guest_transport = UdpTransport::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        let host = LockstepSession::new(Box::new(LossyTransport::new(Box::new(host_transport), latency, 20)), true, 2);
+        let guest = LockstepSession::new(Box::new(LossyTransport::new(Box::new(guest_transport), latency, 20)), false, 2);
+        (VersusMatch::new(host), VersusMatch::new(guest))
+    }
+
+    /// Goes around a square of `side` cells, the snakes start heading right with room ahead
+    struct LoopScript {
+        directions: [Direction; 4],
+        side: Sze,
+        queued_tick: Option<Sze>
+    }
+
+    impl LoopScript {
+        fn new(sim: &Sim, side: Sze) -> Self {
+            let head_pos = sim.get_head_position();
+            let turn = [Direction::Up, Direction::Down]
+                .into_iter()
+                .find(| &direction | (0..side).try_fold(head_pos, | pos, _ | pos.step(direction)).is_some())
+                .unwrap();
+            LoopScript { directions: [Direction::Right, turn, Direction::Left, turn.opposite()], side, queued_tick: None }
+        }
+
+        /// Queues the move of the next tick that has none yet
+        fn push_move(&mut self, versus: &VersusMatch, input_direction: &mut DirectionQueue) {
+            let tick = versus.session.local_inputs.len() as Sze;
+            if self.queued_tick != Some(tick) {
+                self.queued_tick = Some(tick);
+                input_direction.push(self.directions[(tick / self.side) as usize % self.directions.len()]);
+            }
+        }
+    }
+
+    #[test]
+    fn lossy_match_stays_in_sync() {
+        let (mut host, mut guest) = open_lossy_pair();
+        let mut host_sim = Sim::new_simulation();
+        let mut guest_sim = Sim::new_simulation();
+        let mut host_input = DirectionQueue::default();
+        let mut guest_input = DirectionQueue::default();
+        let mut scripts = None;
+        let begin = Instant::now();
+        // Whoever is ahead waits on the other's moves, so both only agree once they're at the same tick
+        while host.next_tick < TEST_TICKS || host.next_tick != guest.next_tick {
+            assert!(begin.elapsed() < Duration::from_secs(30), "stuck at ticks {} / {}", host.next_tick, guest.next_tick);
+            std::thread::sleep(Duration::from_millis(2));
+            let is_host_started = host.try_start(&mut host_sim);
+            let is_guest_started = guest.try_start(&mut guest_sim);
+            if !is_host_started || !is_guest_started {
+                continue;
+            }
+            let (host_script, guest_script) = scripts.get_or_insert_with(| | (LoopScript::new(&host_sim, 6), LoopScript::new(&guest_sim, 5)));
+            host_script.push_move(&host, &mut host_input);
+            guest_script.push_move(&guest, &mut guest_input);
+            host.poll();
+            guest.poll();
+            let last_tick = TEST_TICKS.max(host.next_tick).max(guest.next_tick);
+            let host_steps = u32::from(host.next_tick < last_tick);
+            let guest_steps = u32::from(guest.next_tick < last_tick);
+            host.run_due_steps(host_steps, &mut host_sim, &mut host_input, | sim, input_direction | { sim.step(input_direction); });
+            guest.run_due_steps(guest_steps, &mut guest_sim, &mut guest_input, | sim, input_direction | { sim.step(input_direction); });
+            host.flush();
+            guest.flush();
+        }
+        assert_eq!(host.session.get_desync_tick(), None);
+        assert_eq!(guest.session.get_desync_tick(), None);
+        assert!(!host.session.is_disconnected() && !guest.session.is_disconnected());
+        assert!(host_sim.is_game_running() && guest_sim.is_game_running());
+        let host_opponent = host.get_opponent().unwrap();
+        let guest_opponent = guest.get_opponent().unwrap();
+        assert_eq!(host_sim.get_grid_hash(), guest_opponent.get_grid_hash());
+        assert_eq!(guest_sim.get_grid_hash(), host_opponent.get_grid_hash());
+        assert_eq!(host_sim.get_score(), guest_opponent.get_score());
+        assert_eq!(guest_sim.get_score(), host_opponent.get_score());
+        assert_eq!(host_sim.get_replay().moves, guest_opponent.get_replay().moves);
+        assert_eq!(guest_sim.get_replay().moves, host_opponent.get_replay().moves);
+    }
+}
@@ -0,0 +1,21 @@
+use bevy::prelude::Color;
+use serde::{Serialize, Deserialize};
+
+use crate::cell::CellPos;
+
+const PORTAL_COLORS: [Color; 4] = [Color::FUCHSIA, Color::ORANGE, Color::INDIGO, Color::TURQUOISE];
+
+/// Two linked cells, a snake entering one of them comes out of the other one
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PortalPair {
+    pub a: CellPos,
+    pub b: CellPos
+}
+
+impl PortalPair {
+    pub fn get_other_end(&self, entry: CellPos) -> CellPos {
+        if entry == self.a { self.b } else { self.a }
+    }
+}
+
+pub fn get_portal_color(pair_id: usize) -> Color { PORTAL_COLORS[pair_id % PORTAL_COLORS.len()] }
@@ -0,0 +1,76 @@
+use bevy::prelude::Color;
+use serde::{Serialize, Deserialize};
+
+use crate::{Sze, cell::CellPos};
+
+pub const POWER_UP_KINDS: [PowerUpKind; 5] = [
+    PowerUpKind::Ghost,
+    PowerUpKind::Magnet,
+    PowerUpKind::DoubleScore,
+    PowerUpKind::SlowMotion,
+    PowerUpKind::Shield
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PowerUpKind { Ghost, Magnet, DoubleScore, SlowMotion, Shield }
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PowerUpItem {
+    pub pos: CellPos,
+    pub kind: PowerUpKind
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ActiveEffect {
+    pub kind: PowerUpKind,
+    pub remaining_ticks: Sze
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PowerUpConfig {
+    /// Ticks each effect stays active after being collected
+    pub durations: [Sze; POWER_UP_KINDS.len()],
+    /// Ticks a power-up stays on the board before despawning
+    pub lifetime: Sze,
+    /// Every tick there is a 1 in `spawn_one_in` chance of a power-up showing up
+    pub spawn_one_in: u32,
+    /// Food closer than this (in cells) to the head is pulled in while the magnet is active
+    pub magnet_radius: usize
+}
+
+impl PowerUpKind {
+    pub fn get_color(&self) -> Color {
+        match self {
+            Self::Ghost => Color::SILVER,
+            Self::Magnet => Color::ORANGE_RED,
+            Self::DoubleScore => Color::YELLOW_GREEN,
+            Self::SlowMotion => Color::TEAL,
+            Self::Shield => Color::WHITE
+        }
+    }
+
+    pub fn get_message_id(&self) -> &'static str {
+        match self {
+            Self::Ghost => "power-up-ghost",
+            Self::Magnet => "power-up-magnet",
+            Self::DoubleScore => "power-up-double-score",
+            Self::SlowMotion => "power-up-slow-motion",
+            Self::Shield => "power-up-shield"
+        }
+    }
+}
+
+impl PowerUpConfig {
+    pub fn get_duration(&self, kind: PowerUpKind) -> Sze { self.durations[kind as usize] }
+}
+
+impl Default for PowerUpConfig {
+    fn default() -> Self {
+        PowerUpConfig {
+            durations: [30, 50, 60, 40, 150],
+            lifetime: 50,
+            spawn_one_in: 80,
+            magnet_radius: 4
+        }
+    }
+}
@@ -1,14 +1,29 @@
 use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
 
 use crate::{
     Sim,
+    Sze,
+    SimulationOver,
     grid::GRID_SIZE,
-    cell::CellPos
+    simulation::START_SNAKE_LENGHT,
+    cell::{Cell, CellPos, CellContent},
+    editor::LevelEditor,
+    ghost::Ghost,
+    netplay::VersusMatch,
+    spectate::SpectatorClient,
+    timers::StepTimers,
+    stats::{Stats, OUTCOMES, RECENT_SCORES_LEN}
 };
 
 const BLOCK_SIZE_PX: f32 = 24.0;
 const BORDER_OFFSET: f32 = (BLOCK_SIZE_PX / 2.0) - (BLOCK_SIZE_PX * (GRID_SIZE as f32 / 2.0));
 const HEAD_COLOR: Color = Color::Rgba { red: 0.9, green: 0.8, blue: 0.9, alpha: 1.0 };
+const FATAL_CELL_COLOR: Color = Color::RED;
+/// The fatal cell gets a few nested outlines so it stands out from the cells around it
+const FATAL_CELL_OUTLINES: usize = 3;
+const GHOST_COLOR: Color = Color::Rgba { red: 0.5, green: 0.5, blue: 0.6, alpha: 0.35 };
+const OPPONENT_COLOR: Color = Color::Rgba { red: 0.3, green: 0.4, blue: 0.9, alpha: 0.6 };
 const BLOCK_SIZE: Vec2 = Vec2 { x: BLOCK_SIZE_PX, y: BLOCK_SIZE_PX };
 const BOARD_POS: Vec2 = Vec2 { x: 0.0, y: 0.0 };
 const BOARD_SIZE: Vec2 = Vec2 {
@@ -17,6 +32,25 @@ const BOARD_SIZE: Vec2 = Vec2 {
 };
 
 const SCORE_BOTTOM_MARGIN: f32 = 12.0;
+const TOAST_BOTTOM_MARGIN: f32 = 40.0;
+
+/// Upright bars on both sides of the board, filled from the bottom
+const SIDE_BAR_SIZE: Vec2 = Vec2 { x: BLOCK_SIZE_PX / 2.0, y: BLOCK_SIZE_PX * (GRID_SIZE as f32 / 2.0) };
+const STAMINA_BAR_POS: Vec2 = Vec2 { x: -BORDER_OFFSET + BLOCK_SIZE_PX * 1.5, y: 0.0 };
+const STAMINA_COLOR: Color = Color::GOLD;
+const EXHAUSTED_STAMINA_COLOR: Color = Color::GRAY;
+/// Empties as the combo window runs out
+const COMBO_BAR_POS: Vec2 = Vec2 { x: BORDER_OFFSET - BLOCK_SIZE_PX * 1.5, y: 0.0 };
+const COMBO_COLOR: Color = Color::ORANGE;
+
+/// The stats screen is laid out around the board background, which stays drawn in the middle
+const STATS_TEXT_POS: Vec2 = Vec2 { x: -600.0, y: 300.0 };
+const SCORES_CHART_POS: Vec2 = Vec2 { x: 400.0, y: 120.0 };
+const SCORES_CHART_SIZE: Vec2 = Vec2 { x: 400.0, y: 240.0 };
+const OUTCOMES_CHART_POS: Vec2 = Vec2 { x: 400.0, y: -120.0 };
+const OUTCOMES_CHART_SIZE: Vec2 = Vec2 { x: 400.0, y: 40.0 };
+/// Gizmos only draw outlines, filled shapes are drawn as lines this far apart
+const FILL_LINE_SPACING: f32 = 2.0;
 
 pub fn get_score_transform() -> Transform {
     Transform::from_translation(Vec3 {
@@ -26,18 +60,160 @@ pub fn get_score_transform() -> Transform {
     })
 }
 
+pub fn get_timer_transform() -> Transform {
+    Transform::from_translation(Vec3 {
+        x: 0.0,
+        y: SCORE_BOTTOM_MARGIN - BORDER_OFFSET,
+        z: 1.0
+    })
+}
+
+/// Under the board, the text is anchored on its top edge
+pub fn get_split_transform() -> Transform {
+    Transform::from_translation(Vec3 {
+        x: 0.0,
+        y: BORDER_OFFSET - SCORE_BOTTOM_MARGIN,
+        z: 1.0
+    })
+}
+
+/// Under the board on the left, the text is anchored on its top left corner
+pub fn get_speed_transform() -> Transform {
+    Transform::from_translation(Vec3 {
+        x: BORDER_OFFSET,
+        y: BORDER_OFFSET - SCORE_BOTTOM_MARGIN,
+        z: 1.0
+    })
+}
+
+pub fn get_effects_transform() -> Transform {
+    Transform::from_translation(Vec3 {
+        x: BORDER_OFFSET,
+        y: SCORE_BOTTOM_MARGIN - BORDER_OFFSET,
+        z: 1.0
+    })
+}
+
+/// Above the score line, the text is anchored on its bottom edge
+pub fn get_toast_transform() -> Transform {
+    Transform::from_translation(Vec3 {
+        x: 0.0,
+        y: SCORE_BOTTOM_MARGIN - BORDER_OFFSET + TOAST_BOTTOM_MARGIN,
+        z: 2.0
+    })
+}
+
+/// Left of the board, the text is anchored on its right edge
+pub fn get_editor_text_transform() -> Transform {
+    Transform::from_translation(Vec3 {
+        x: BORDER_OFFSET - BLOCK_SIZE_PX,
+        y: 0.0,
+        z: 1.0
+    })
+}
+
+pub fn get_stats_text_transform() -> Transform { Transform::from_xyz(STATS_TEXT_POS.x, STATS_TEXT_POS.y, 1.0) }
+
 pub fn get_background_shape() -> shape::Box {
     shape::Box::new(BOARD_SIZE.x, BOARD_SIZE.y, 1.0)
 }
 
-pub fn render_game(game: ResMut<Sim>, mut gizmos: Gizmos) {
-    game.get_occupied_cells().iter().for_each(| cell | {
-        render_cell(cell.position, cell.content.get_color(), &mut gizmos);
+pub fn spawn_background(commands: &mut Commands, meshes: &mut Assets<Mesh>, materials: &mut Assets<ColorMaterial>) {
+    commands.spawn(MaterialMesh2dBundle {
+        mesh: meshes.add(get_background_shape().into()).into(),
+        material: materials.add(ColorMaterial::from(Color::Rgba { red: 0.1, green: 0.7, blue: 0.2, alpha: 0.7 })),
+        ..default()
     });
-    render_cell(game.get_head_position(), HEAD_COLOR, &mut gizmos);
+}
+
+pub fn render_game(game: ResMut<Sim>, ghost: Res<Ghost>, versus: Option<Res<VersusMatch>>, mut gizmos: Gizmos) {
+    ghost.get_snake_cells().into_iter().for_each(| pos | render_cell(pos, GHOST_COLOR, &mut gizmos));
+    if let Some(versus) = versus {
+        versus.get_opponent_snake_cells().into_iter().for_each(| pos | render_cell(pos, OPPONENT_COLOR, &mut gizmos));
+    }
+    render_board(&game.get_occupied_cells(), game.get_head_position(), game.get_game_over(), &mut gizmos);
+}
+
+/// Draws the board a viewer is watching, just the outline until the first snapshot came in
+pub fn render_spectated_game(client: Res<SpectatorClient>, mut gizmos: Gizmos) {
+    match client.get_status() {
+        Some(status) => render_board(&client.get_cells(), status.head, status.game_over, &mut gizmos),
+        None => gizmos.rect_2d(BOARD_POS, 0.0, BOARD_SIZE, Color::BLACK)
+    }
+}
+
+fn render_board(cells: &[Cell], head_pos: CellPos, game_over: Option<SimulationOver>, gizmos: &mut Gizmos) {
+    cells.iter().for_each(| cell | render_cell(cell.position, cell.content.get_color(), gizmos));
+    render_cell(head_pos, HEAD_COLOR, gizmos);
+    if let Some(game_over) = game_over.filter(| game_over | !game_over.reason.is_win()) {
+        let rect_pos = translate_grid_pos_to_screen(game_over.pos);
+        for outline in 0..FATAL_CELL_OUTLINES {
+            let size = BLOCK_SIZE - Vec2::splat(2.0 * outline as f32);
+            gizmos.rect_2d(rect_pos, 0.0, size, FATAL_CELL_COLOR);
+        }
+    }
     gizmos.rect_2d(BOARD_POS, 0.0, BOARD_SIZE, Color::BLACK);
 }
 
+pub fn render_stamina(step_timers: Res<StepTimers>, mut gizmos: Gizmos) {
+    let color = if step_timers.is_exhausted() { EXHAUSTED_STAMINA_COLOR } else { STAMINA_COLOR };
+    let size = Vec2::new(SIDE_BAR_SIZE.x, SIDE_BAR_SIZE.y * step_timers.get_stamina());
+    render_filled_rect(STAMINA_BAR_POS - SIDE_BAR_SIZE / 2.0, size, color, &mut gizmos);
+    gizmos.rect_2d(STAMINA_BAR_POS, 0.0, SIDE_BAR_SIZE, Color::BLACK);
+}
+
+pub fn render_combo(game: Res<Sim>, mut gizmos: Gizmos) {
+    let remaining = game.get_combo().get_remaining_fraction(game.get_tick());
+    let size = Vec2::new(SIDE_BAR_SIZE.x, SIDE_BAR_SIZE.y * remaining);
+    render_filled_rect(COMBO_BAR_POS - SIDE_BAR_SIZE / 2.0, size, COMBO_COLOR, &mut gizmos);
+    gizmos.rect_2d(COMBO_BAR_POS, 0.0, SIDE_BAR_SIZE, Color::BLACK);
+}
+
+pub fn render_level_editor(editor: Res<LevelEditor>, mut gizmos: Gizmos) {
+    editor.get_tile_colors().into_iter().for_each(| (pos, color) | render_cell(pos, color, &mut gizmos));
+    if let Some(head_pos) = editor.get_level().start {
+        for offset in 1..START_SNAKE_LENGHT {
+            let body_pos = CellPos { x: head_pos.x - offset, y: head_pos.y };
+            let body_color = CellContent::SnakeBody { age: offset as Sze }.get_color();
+            render_cell(body_pos, body_color, &mut gizmos);
+        }
+        render_cell(head_pos, HEAD_COLOR, &mut gizmos);
+    }
+    gizmos.rect_2d(BOARD_POS, 0.0, BOARD_SIZE, Color::BLACK);
+}
+
+/// Bars of the recent scores over a stacked bar of how all games ended
+pub fn render_stats(stats: Res<Stats>, mut gizmos: Gizmos) {
+    let record = &stats.lifetime;
+    let max_score = record.recent_scores.iter().copied().max().unwrap_or(0).max(1) as f32;
+    let slot_width = SCORES_CHART_SIZE.x / RECENT_SCORES_LEN as f32;
+    let chart_bottom_left = SCORES_CHART_POS - SCORES_CHART_SIZE / 2.0;
+    for (index, &score) in record.recent_scores.iter().enumerate() {
+        let height = SCORES_CHART_SIZE.y * score as f32 / max_score;
+        let bottom_left = chart_bottom_left + Vec2::new(slot_width * index as f32 + slot_width * 0.1, 0.0);
+        render_filled_rect(bottom_left, Vec2::new(slot_width * 0.8, height), Color::DARK_GREEN, &mut gizmos);
+    }
+    gizmos.rect_2d(SCORES_CHART_POS, 0.0, SCORES_CHART_SIZE, Color::BLACK);
+
+    let games = OUTCOMES.iter().map(| &outcome | record.get_outcome_count(outcome)).sum::<Sze>().max(1) as f32;
+    let mut left = OUTCOMES_CHART_POS.x - OUTCOMES_CHART_SIZE.x / 2.0;
+    for outcome in OUTCOMES {
+        let width = OUTCOMES_CHART_SIZE.x * record.get_outcome_count(outcome) as f32 / games;
+        let bottom_left = Vec2::new(left, OUTCOMES_CHART_POS.y - OUTCOMES_CHART_SIZE.y / 2.0);
+        render_filled_rect(bottom_left, Vec2::new(width, OUTCOMES_CHART_SIZE.y), outcome.get_color(), &mut gizmos);
+        left += width;
+    }
+    gizmos.rect_2d(OUTCOMES_CHART_POS, 0.0, OUTCOMES_CHART_SIZE, Color::BLACK);
+}
+
+fn render_filled_rect(bottom_left: Vec2, size: Vec2, color: Color, gizmos: &mut Gizmos) {
+    let mut x = bottom_left.x;
+    while x < bottom_left.x + size.x {
+        gizmos.line_2d(Vec2::new(x, bottom_left.y), Vec2::new(x, bottom_left.y + size.y), color);
+        x += FILL_LINE_SPACING;
+    }
+}
+
 fn render_cell(cell_pos: CellPos, color: Color, gizmos: &mut Gizmos) {
     let rect_pos = translate_grid_pos_to_screen(cell_pos);
     gizmos.rect_2d(rect_pos, 0.0, BLOCK_SIZE, color);
@@ -48,3 +224,11 @@ fn translate_grid_pos_to_screen(cell: CellPos) -> Vec2 {
     let y: f32 = BLOCK_SIZE_PX * (cell.y as f32) + BORDER_OFFSET;
     Vec2 { x, y }
 }
+
+/// Inverse of `translate_grid_pos_to_screen`, `None` if `pos` is outside the board
+pub fn translate_screen_pos_to_grid(pos: Vec2) -> Option<CellPos> {
+    let x = ((pos.x - BORDER_OFFSET) / BLOCK_SIZE_PX).round();
+    let y = ((pos.y - BORDER_OFFSET) / BLOCK_SIZE_PX).round();
+    let is_on_board = | coordinate: f32 | coordinate >= 0.0 && coordinate < GRID_SIZE as f32;
+    (is_on_board(x) && is_on_board(y)).then_some(CellPos { x: x as usize, y: y as usize })
+}
@@ -0,0 +1,43 @@
+use serde::{Serialize, Deserialize};
+
+use crate::{Sze, utils::Direction, powerup::PowerUpKind, rules::GameRules};
+
+/// A finished or in-progress game, recorded so it can be simulated again
+///
+/// `seed` feeds the simulation RNG, `rules` are the ones the game started with and `moves`
+/// holds the direction taken on every tick, which is all that's needed to step an identical
+/// `Sim`. `events` is a log of notable things that happened along the way.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub rules: GameRules,
+    pub moves: Vec<Direction>,
+    pub events: Vec<ReplayEntry>
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ReplayEntry {
+    pub tick: Sze,
+    pub event: ReplayEvent
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ReplayEvent {
+    PowerUpCollected(PowerUpKind),
+    EffectExpired(PowerUpKind),
+    ShieldUsed,
+    FoodCountChanged(usize),
+    BoostChanged(bool)
+}
+
+impl Replay {
+    pub fn new(seed: u64, rules: GameRules) -> Self {
+        Replay { seed, rules, moves: vec!(), events: vec!() }
+    }
+
+    pub fn record_move(&mut self, direction: Direction) { self.moves.push(direction); }
+
+    pub fn record_event(&mut self, tick: Sze, event: ReplayEvent) {
+        self.events.push(ReplayEntry { tick, event });
+    }
+}
@@ -0,0 +1,75 @@
+use std::{collections::VecDeque, time::Duration};
+use bevy::prelude::{Resource, Timer, TimerMode};
+
+use crate::{
+    ghost::{Ghost, GhostSnapshot},
+    simulation::{Sim, SimSnapshot},
+    timers::{StepTimers, SpeedState}
+};
+
+/// Steps kept to go back to, 20 seconds at the starting speed of Normal and 4 at its fastest
+const REWIND_CAPACITY: usize = 40;
+/// How fast time goes backwards while the rewind key is held
+const REWIND_STEP_SECONDS: f32 = 0.1;
+
+/// The last few states of the simulation, each with the speed it had and where the ghost was
+///
+/// Snapshots left over from another game are dropped instead of being rewound to, so the buffer
+/// doesn't need to be cleared when a game restarts.
+#[derive(Resource)]
+pub struct RewindBuffer {
+    snapshots: VecDeque<(SimSnapshot, SpeedState, Option<GhostSnapshot>)>,
+    step_timer: Timer,
+    is_rewinding: bool
+}
+
+impl RewindBuffer {
+    /// Call right before every step
+    pub fn record(&mut self, sim: &mut Sim, step_timers: &StepTimers, ghost: &mut Ghost) {
+        self.snapshots.retain(| (snapshot, _, _) | snapshot.precedes(sim));
+        if self.snapshots.len() == REWIND_CAPACITY {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back((sim.take_snapshot(), step_timers.get_speed(), ghost.take_snapshot()));
+    }
+
+    /// Returns true when a step back is due, right away on a fresh press then every `REWIND_STEP_SECONDS`
+    pub fn update(&mut self, is_key_held: bool, delta: Duration) -> bool {
+        if !is_key_held {
+            self.is_rewinding = false;
+            return false;
+        }
+        let is_new_press = !self.is_rewinding;
+        self.is_rewinding = true;
+        if is_new_press {
+            self.step_timer.reset();
+        }
+        is_new_press || self.step_timer.tick(delta).just_finished()
+    }
+
+    pub fn is_rewinding(&self) -> bool { self.is_rewinding }
+
+    /// Goes back one step, returns false once there is nothing left to rewind
+    pub fn rewind_step(&mut self, sim: &mut Sim, step_timers: &mut StepTimers, ghost: &mut Ghost) -> bool {
+        while let Some((snapshot, speed, ghost_snapshot)) = self.snapshots.pop_back() {
+            if snapshot.precedes(sim) {
+                sim.restore_snapshot(snapshot);
+                ghost.restore_snapshot(ghost_snapshot);
+                step_timers.set_speed(speed);
+                step_timers.reset_tick_progress();
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        RewindBuffer {
+            snapshots: VecDeque::with_capacity(REWIND_CAPACITY),
+            step_timer: Timer::from_seconds(REWIND_STEP_SECONDS, TimerMode::Repeating),
+            is_rewinding: false
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    Sze,
+    food::FoodConfig,
+    powerup::PowerUpConfig,
+    hazard::HazardConfig,
+    level::Level,
+    difficulty::Difficulty
+};
+
+/// Everything besides the seed that shapes a game, fixed when the game starts
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GameRules {
+    pub food_config: FoodConfig,
+    pub power_up_config: PowerUpConfig,
+    pub hazard_config: HazardConfig,
+    pub random_portal_pairs: usize,
+    pub level: Option<Level>,
+    pub time_attack: Option<TimeAttackRules>,
+    #[serde(default)]
+    pub difficulty: Difficulty
+}
+
+/// Play against the clock, the run ends when `budget_ticks` run out
+///
+/// Time is kept in ticks and not wall-clock time so a replay always runs out at the same step, how
+/// long a tick takes changes with the speed and boost.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimeAttackRules {
+    pub budget_ticks: Sze,
+    /// Added to the remaining time for every piece of food eaten
+    pub bonus_ticks_per_food: Sze
+}
+
+impl Default for TimeAttackRules {
+    fn default() -> Self {
+        TimeAttackRules {
+            budget_ticks: 480,
+            bonus_ticks_per_food: 12
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::{fmt, fs, io, path::Path};
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    input::{DirectionQueue, PlayerInput},
+    simulation::Sim,
+    timers::{StepTimers, SpeedState}
+};
+
+/// Bump whenever a change to `Sim` or anything inside it changes the saved format
+pub const SAVE_FORMAT_VERSION: u32 = 3;
+
+const SAVE_DIR: &str = "saves";
+const SAVE_PATH: &str = "saves/game.ron";
+
+/// A game in progress, written as RON to `SAVE_PATH`
+///
+/// The file also holds the `SAVE_FORMAT_VERSION` it was written with, checked before anything else is read.
+#[derive(Deserialize)]
+pub struct SavedGame {
+    sim: Sim,
+    speed: SpeedState,
+    input_direction: DirectionQueue
+}
+
+/// Same layout as `SavedGame`, borrowing everything so saving doesn't need to clone the `Sim`
+#[derive(Serialize)]
+struct SavedGameRef<'a> {
+    version: u32,
+    sim: &'a Sim,
+    speed: SpeedState,
+    input_direction: &'a DirectionQueue
+}
+
+/// Read first on its own, so a save from another version is reported as such and not as corrupted
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Corrupted(String),
+    UnsupportedVersion(u32)
+}
+
+impl SavedGame {
+    pub fn exists() -> bool { Path::new(SAVE_PATH).exists() }
+
+    pub fn save(sim: &Sim, step_timers: &StepTimers, player_input: &PlayerInput) -> Result<(), SaveError> {
+        let saved_game = SavedGameRef {
+            version: SAVE_FORMAT_VERSION,
+            sim,
+            speed: step_timers.get_speed(),
+            input_direction: &player_input.input_direction
+        };
+        let contents = ron::ser::to_string_pretty(&saved_game, ron::ser::PrettyConfig::default())
+            .map_err(| error | SaveError::Corrupted(error.to_string()))?;
+        fs::create_dir_all(SAVE_DIR)?;
+        fs::write(SAVE_PATH, contents)?;
+        Ok(())
+    }
+
+    pub fn load() -> Result<Self, SaveError> {
+        let contents = fs::read_to_string(SAVE_PATH)?;
+        let header: SaveHeader = ron::from_str(&contents).map_err(| error | SaveError::Corrupted(error.to_string()))?;
+        if header.version != SAVE_FORMAT_VERSION {
+            return Err(SaveError::UnsupportedVersion(header.version));
+        }
+        let saved_game: SavedGame = ron::from_str(&contents).map_err(| error | SaveError::Corrupted(error.to_string()))?;
+        if !saved_game.sim.is_consistent() {
+            return Err(SaveError::Corrupted("the game state doesn't add up".to_string()));
+        }
+        Ok(saved_game)
+    }
+
+    /// A save is only resumed once
+    pub fn delete() {
+        if let Err(error) = fs::remove_file(SAVE_PATH) {
+            if error.kind() != io::ErrorKind::NotFound {
+                eprintln!("Couldn't delete {}: {}", SAVE_PATH, error);
+            }
+        }
+    }
+
+    pub fn restore(self, sim: &mut Sim, step_timers: &mut StepTimers, player_input: &mut PlayerInput) {
+        *sim = self.sim;
+        step_timers.reset_tick_speed(sim.get_difficulty());
+        step_timers.set_speed(self.speed);
+        player_input.input_direction = self.input_direction;
+    }
+}
+
+impl From<io::Error> for SaveError {
+    fn from(error: io::Error) -> Self { SaveError::Io(error) }
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "could not access the save file: {}", error),
+            Self::Corrupted(reason) => write!(f, "the save file is corrupted: {}", reason),
+            Self::UnsupportedVersion(version) => write!(
+                f, "the save file is version {}, this game reads version {}", version, SAVE_FORMAT_VERSION
+            )
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use bevy::prelude::Resource;
+
+use crate::{food::MAX_FOOD_COUNT, difficulty::Difficulty, locale::{Language, LANGUAGES}};
+
+pub const MAX_RANDOM_PORTAL_PAIRS: usize = 3;
+
+#[derive(Resource)]
+pub struct Settings {
+    pub sound_enabled: bool,
+    pub food_count: usize,
+    pub portal_pairs: usize,
+    pub hazards_enabled: bool,
+    pub time_attack_enabled: bool,
+    pub practice_mode: bool,
+    pub difficulty: Difficulty,
+    pub language: &'static Language
+}
+
+impl Settings {
+    pub fn toggle_sound(&mut self) { self.sound_enabled = !self.sound_enabled; }
+
+    pub fn cycle_food_count(&mut self) { self.food_count = self.food_count % MAX_FOOD_COUNT + 1; }
+
+    pub fn toggle_hazards(&mut self) { self.hazards_enabled = !self.hazards_enabled; }
+
+    pub fn toggle_time_attack(&mut self) { self.time_attack_enabled = !self.time_attack_enabled; }
+
+    pub fn toggle_practice_mode(&mut self) { self.practice_mode = !self.practice_mode; }
+
+    pub fn cycle_difficulty(&mut self) { self.difficulty = self.difficulty.next(); }
+
+    pub fn cycle_language(&mut self) { self.language = self.language.next(); }
+
+    pub fn cycle_portal_pairs(&mut self) { self.portal_pairs = (self.portal_pairs + 1) % (MAX_RANDOM_PORTAL_PAIRS + 1); }
+
+    /// The settings that go into the game's rules, compared to tell whether any of them changed
+    pub fn get_rule_settings(&self) -> (usize, Difficulty, usize, bool, bool) {
+        (self.food_count, self.difficulty, self.portal_pairs, self.hazards_enabled, self.time_attack_enabled)
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings { sound_enabled: true, food_count: 1, portal_pairs: 0, hazards_enabled: false, time_attack_enabled: false, practice_mode: false, difficulty: Difficulty::default(), language: &LANGUAGES[0] }
+    }
+}
@@ -1,198 +1,489 @@
-use rand::{rngs::ThreadRng, Rng, seq::SliceRandom};
+use rand::{Rng, SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
 use bevy::prelude::{Resource, EventWriter};
+use serde::{Serialize, Deserialize};
 
 use crate::{
     Sze,
     FoodEaten,
     SimulationOver,
-    utils::{min, Direction},
+    SimulationTicked,
+    utils::{Direction, DIRECTIONS},
     grid::{Grid, GRID_SIZE},
     cell::{Cell, CellPos, CellContent},
+    food::{FoodConfig, FoodItem, FoodKind, FOOD_KINDS, MAX_FOOD_COUNT},
+    powerup::{ActiveEffect, PowerUpConfig, PowerUpItem, PowerUpKind, POWER_UP_KINDS},
+    replay::{Replay, ReplayEvent},
+    rules::{GameRules, TimeAttackRules},
+    level::Level,
+    portal::PortalPair,
+    hazard::{Hazard, HazardConfig, HazardKind, get_patrol_path},
     input::DirectionQueue,
+    difficulty::Difficulty,
+    combo::Combo,
 };
 
 pub const START_SNAKE_LENGHT: usize = 3;
 const SCORE_BASE: Sze = 100;
+/// Extra score for food eaten while boosting, in percent
+const BOOST_SCORE_BONUS_PERCENT: Sze = 50;
 
-#[derive(Debug, PartialEq, Clone)]
-pub enum SimState { Running, Win, Loss }
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum SimState { Running, Win, Loss, Timeout }
 
-#[derive(Resource)]
+/// Why a game ended, every reason but `BoardFull` loses it
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum EndReason { HitWall, BitSelf, HitObstacle, Timeout, BoardFull }
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
 pub struct Sim {
     eaten_food: Sze,
+    snake_length: Sze,
     score: Sze,
     score_multiplier: Sze,
     neck_direction: Direction,
     game_state: SimState,
+    /// How the game ended, kept so the board can show it
+    #[serde(default)]
+    game_over: Option<SimulationOver>,
     grid: Grid,
     head_pos: CellPos,
     tail_pos: CellPos,
-    food_pos: CellPos
+    food_items: Vec<FoodItem>,
+    power_up: Option<PowerUpItem>,
+    active_effects: Vec<ActiveEffect>,
+    /// Older body segments the head passed over while the ghost effect was active
+    ghost_overlaps: Vec<SnakeBody>,
+    portals: Vec<PortalPair>,
+    hazards: Vec<Hazard>,
+    rules: GameRules,
+    /// Only counts down in time attack games
+    remaining_ticks: Option<Sze>,
+    tick: Sze,
+    rng: ChaCha8Rng,
+    replay: Replay,
+    /// A rewound game doesn't count for high scores
+    #[serde(default)]
+    rewind_used: bool,
+    #[serde(default)]
+    is_boosting: bool,
+    #[serde(default)]
+    combo: Combo
+}
+
+/// A `Sim` as it was before a step, without the replay which only ever grows
+pub struct SimSnapshot {
+    sim: Sim,
+    seed: u64,
+    replay_moves: usize,
+    replay_events: usize
 }
 
+#[derive(Clone, Copy, Serialize, Deserialize)]
 struct SnakeBody {
     pos: CellPos,
     age: Sze
 }
 
+/// What happened during one step, see `Sim::step`
+#[derive(Default)]
+pub struct StepEvents {
+    pub food_eaten: Option<FoodEaten>,
+    pub game_over: Option<SimulationOver>
+}
+
 struct GameInitialization {
     eaten_food: Sze,
+    snake_length: Sze,
     score: Sze,
     score_multiplier: Sze,
     neck_direction: Direction,
     game_state: SimState,
     head_pos: CellPos,
-    tail_pos: CellPos,
-    food_pos: CellPos
+    tail_pos: CellPos
 }
 
 impl Sim {
     pub fn new_simulation() -> Self {
+        let seed = rand::random();
         let mut grid = Grid::new_empty_grid();
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
         let GameInitialization {
             eaten_food,
+            snake_length,
             score,
             score_multiplier,
             neck_direction,
             game_state,
             head_pos,
-            tail_pos,
-            food_pos
-        } = get_random_sim_start(&mut grid);
-        Sim {
+            tail_pos
+        } = get_random_sim_start(&mut grid, &mut rng, None);
+        let rules = GameRules::default();
+        let replay = Replay::new(seed, rules.clone());
+        let mut sim = Sim {
             eaten_food,
+            snake_length,
             score_multiplier,
             score,
             neck_direction,
             game_state,
+            game_over: None,
             grid,
             head_pos,
             tail_pos,
-            food_pos,
+            food_items: vec!(),
+            power_up: None,
+            active_effects: vec!(),
+            ghost_overlaps: vec!(),
+            portals: vec!(),
+            hazards: vec!(),
+            rules,
+            remaining_ticks: None,
+            tick: 0,
+            is_boosting: false,
+            combo: Combo::default(),
+            rng,
+            replay,
+            rewind_used: false
+        };
+        sim.top_up_food();
+        sim
+    }
+
+    pub fn set_food_config(&mut self, food_config: FoodConfig) { self.rules.food_config = food_config; }
+
+    /// Takes effect from the next step on, food already on the board is never removed
+    pub fn set_food_count(&mut self, food_count: usize) {
+        let food_count = food_count.clamp(1, MAX_FOOD_COUNT);
+        if food_count != self.rules.food_config.food_count {
+            self.rules.food_config.food_count = food_count;
+            self.replay.record_event(self.tick, ReplayEvent::FoodCountChanged(food_count));
         }
     }
 
-    pub fn reset_new_game(&mut self) {
+    /// Takes effect from the next step on, food eaten while boosting scores more
+    pub fn set_boosting(&mut self, is_boosting: bool) {
+        if is_boosting != self.is_boosting {
+            self.is_boosting = is_boosting;
+            self.replay.record_event(self.tick, ReplayEvent::BoostChanged(is_boosting));
+        }
+    }
+
+    pub fn set_power_up_config(&mut self, power_up_config: PowerUpConfig) { self.rules.power_up_config = power_up_config; }
+
+    /// Takes effect when the next game starts
+    pub fn set_random_portal_pairs(&mut self, random_portal_pairs: usize) {
+        self.rules.random_portal_pairs = random_portal_pairs;
+    }
+
+    /// Takes effect when the next game starts
+    pub fn set_level(&mut self, level: Option<Level>) { self.rules.level = level; }
+
+    /// Takes effect when the next game starts
+    pub fn set_hazard_config(&mut self, hazard_config: HazardConfig) { self.rules.hazard_config = hazard_config; }
+
+    /// Takes effect when the next game starts, `None` plays without a time limit
+    pub fn set_time_attack(&mut self, time_attack: Option<TimeAttackRules>) { self.rules.time_attack = time_attack; }
+
+    /// Takes effect when the next game starts
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) { self.rules.difficulty = difficulty; }
+
+    /// The difficulty the current game started with
+    pub fn get_difficulty(&self) -> Difficulty { self.replay.rules.difficulty }
+
+    /// Takes effect when the next game starts
+    pub fn set_rules(&mut self, rules: GameRules) { self.rules = rules; }
+
+    pub fn get_rules(&self) -> &GameRules { &self.rules }
+
+    pub fn reset_new_game(&mut self) { self.reset_with_seed(rand::random()); }
+
+    pub fn reset_with_seed(&mut self, seed: u64) {
         self.grid.clear_grid();
-        let new_game = get_random_sim_start(&mut self.grid);
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        let level_start = self.rules.level.as_ref().and_then(| level | level.start);
+        let new_game = get_random_sim_start(&mut self.grid, &mut self.rng, level_start);
         self.eaten_food = new_game.eaten_food;
+        self.snake_length = new_game.snake_length;
         self.score = new_game.score;
         self.score_multiplier = new_game.score_multiplier;
         self.neck_direction = new_game.neck_direction;
         self.game_state = new_game.game_state;
+        self.game_over = None;
         self.head_pos = new_game.head_pos;
         self.tail_pos = new_game.tail_pos;
-        self.food_pos = new_game.food_pos;
+        self.food_items.clear();
+        self.power_up = None;
+        self.active_effects.clear();
+        self.ghost_overlaps.clear();
+        self.tick = 0;
+        self.remaining_ticks = self.rules.time_attack.as_ref().map(| time_attack | time_attack.budget_ticks);
+        self.replay = Replay::new(seed, self.rules.clone());
+        self.rewind_used = false;
+        self.is_boosting = false;
+        self.combo = Combo::default();
+        self.place_portals();
+        self.place_hazards();
+        self.fill_sealed_pockets();
+        self.top_up_food();
     }
 
     pub fn run_next_step(
         &mut self,
         input_direction: &mut DirectionQueue,
-        score_writer: EventWriter<FoodEaten>,
-        mut game_over_writer: EventWriter<SimulationOver>
+        score_writer: &mut EventWriter<FoodEaten>,
+        game_over_writer: &mut EventWriter<SimulationOver>,
+        tick_writer: &mut EventWriter<SimulationTicked>
     ) {
-        self.age_snake_body();
-        self.move_snake_head(input_direction);
-        if !self.is_game_running() {
-            game_over_writer.send(SimulationOver { win: false });
-            return;
+        let StepEvents { food_eaten, game_over } = self.step(input_direction);
+        tick_writer.send(SimulationTicked {
+            seed: self.replay.seed,
+            tick: self.tick,
+            score: self.score,
+            snake_length: self.snake_length,
+            eaten_food: self.eaten_food,
+            is_boosting: self.is_boosting,
+            combo_chain: self.combo.get_chain(self.tick)
+        });
+        if let Some(event) = food_eaten {
+            score_writer.send(event);
+        }
+        if let Some(event) = game_over {
+            game_over_writer.send(event);
+        }
+    }
+
+    /// Same as `run_next_step` but hands the events back instead of sending them, for simulations
+    /// that run next to the game like replay ghosts
+    pub fn step(&mut self, input_direction: &mut DirectionQueue) -> StepEvents {
+        let mut events = StepEvents::default();
+        self.tick += 1;
+        self.tick_effects();
+        if self.tick_time_attack_clock() {
+            events.game_over = Some(self.end_game(EndReason::Timeout, self.head_pos));
+            return events;
         }
-        if self.was_food_eaten() {
-            let could_spawn_food = self.spawn_food();
-            self.update_and_notifiy_score(score_writer);
-            if !could_spawn_food {
-                self.game_state= SimState::Win;
-                game_over_writer.send(SimulationOver { win: true });
+        let move_dir = get_move_direction(input_direction, &self.neck_direction);
+        self.replay.record_move(move_dir);
+        let head_pos = match self.get_next_head_pos(move_dir) {
+            Ok(head_pos) => head_pos,
+            Err((reason, pos)) => {
+                if self.consume_effect(PowerUpKind::Shield) {
+                    self.replay.record_event(self.tick, ReplayEvent::ShieldUsed);
+                } else {
+                    events.game_over = Some(self.end_game(reason, pos));
+                }
+                return events;
             }
+        };
+        self.age_cells();
+        self.move_snake_head(head_pos, move_dir);
+        if let Some(eaten_food) = self.take_eaten_food() {
+            self.update_snake_length(eaten_food.kind);
+            events.food_eaten = Some(self.update_and_notifiy_score(eaten_food.kind));
+            self.add_time_attack_bonus();
         }
+        self.collect_power_up();
         self.move_snake_tail();
-        self.log_game_state_if_finished();
+        if self.step_hazards() {
+            if self.consume_effect(PowerUpKind::Shield) {
+                self.replay.record_event(self.tick, ReplayEvent::ShieldUsed);
+            } else {
+                events.game_over = Some(self.end_game(EndReason::HitObstacle, self.head_pos));
+                return events;
+            }
+        }
+        self.expire_food();
+        self.expire_power_up();
+        if self.has_effect(PowerUpKind::Magnet) {
+            self.pull_food_towards_head();
+        }
+        self.top_up_food();
+        // Sealed pockets are filled in, so the empty cells food is drawn from are all there is left to fill
+        if self.food_items.is_empty() && self.grid.get_empty_cell_count() == 0 {
+            events.game_over = Some(self.end_game(EndReason::BoardFull, self.head_pos));
+        } else {
+            self.spawn_bonus_food_at_random();
+            self.spawn_power_up_at_random();
+        }
+        events
     }
 
-    fn update_and_notifiy_score(&mut self, mut score_writer: EventWriter<FoodEaten>) {
+    fn update_and_notifiy_score(&mut self, food_kind: FoodKind) -> FoodEaten {
         self.eaten_food += 1;
         if self.eaten_food % 10 == 0 {
             self.score_multiplier += 1;
         }
-        self.score += SCORE_BASE * self.score_multiplier;
-        score_writer.send(FoodEaten { new_score: self.score, pieces_eaten: self.eaten_food });
+        let effect_multiplier = if self.has_effect(PowerUpKind::DoubleScore) { 2 } else { 1 };
+        let combo_multiplier = self.combo.add_food(self.tick, self.is_boosting);
+        let food_score = self.rules.food_config.get_rule(food_kind).score * self.score_multiplier * effect_multiplier * combo_multiplier;
+        let boost_bonus = if self.is_boosting { food_score * BOOST_SCORE_BONUS_PERCENT / 100 } else { 0 };
+        self.score += food_score + boost_bonus;
+        FoodEaten { new_score: self.score, pieces_eaten: self.eaten_food, food_kind }
     }
 
-    fn age_snake_body(&mut self) {
+    fn add_time_attack_bonus(&mut self) {
+        if let (Some(remaining_ticks), Some(time_attack)) = (&mut self.remaining_ticks, &self.rules.time_attack) {
+            *remaining_ticks += time_attack.bonus_ticks_per_food;
+        }
+    }
+
+    /// Returns true when the time attack clock runs out
+    fn tick_time_attack_clock(&mut self) -> bool {
+        match &mut self.remaining_ticks {
+            Some(remaining_ticks) => {
+                *remaining_ticks = remaining_ticks.saturating_sub(1);
+                *remaining_ticks == 0
+            },
+            None => false
+        }
+    }
+
+    fn update_snake_length(&mut self, food_kind: FoodKind) {
+        let new_length = self.snake_length as i64 + self.rules.food_config.get_rule(food_kind).length_change as i64;
+        self.snake_length = new_length.max(START_SNAKE_LENGHT as i64) as Sze;
+    }
+
+    fn age_cells(&mut self) {
         self.get_occupied_cells().iter().for_each( | &cell | {
-            match cell.content {
-                CellContent::Food => {},
-                CellContent::SnakeBody { age } => {
-                    self.grid.set_cell(Cell {
-                        position: cell.position,
-                        content: CellContent::SnakeBody { age: age + 1 }
-                    });
+            let content = match cell.content {
+                CellContent::Food { kind, age } => CellContent::Food { kind, age: age + 1 },
+                CellContent::PowerUp { kind, age } => CellContent::PowerUp { kind, age: age + 1 },
+                CellContent::SnakeBody { age } => CellContent::SnakeBody { age: age + 1 },
+                CellContent::Portal { .. } | CellContent::Hazard { .. } => return
+            };
+            self.grid.set_cell(Cell { position: cell.position, content });
+        });
+        self.ghost_overlaps.iter_mut().for_each(| segment | segment.age += 1);
+    }
+
+    fn expire_food(&mut self) {
+        let (expired, fresh): (Vec<FoodItem>, Vec<FoodItem>) = self.food_items
+            .iter()
+            .partition(| &&food | self.is_food_expired(food));
+        expired.iter().for_each(| food | self.grid.clear_cell(food.pos));
+        self.food_items = fresh;
+    }
+
+    fn is_food_expired(&self, food: FoodItem) -> bool {
+        let lifetime = self.rules.food_config.get_rule(food.kind).lifetime;
+        match (lifetime, self.grid.get_cell_content(food.pos)) {
+            (Some(lifetime), Some(CellContent::Food { age, .. })) => age >= lifetime,
+            _ => false
+        }
+    }
+
+    /// Moves every piece of food within `magnet_radius` one cell closer to the head
+    fn pull_food_towards_head(&mut self) {
+        let radius = self.rules.power_up_config.magnet_radius as i64;
+        for index in 0..self.food_items.len() {
+            let food_pos = self.food_items[index].pos;
+            let dx = self.head_pos.x as i64 - food_pos.x as i64;
+            let dy = self.head_pos.y as i64 - food_pos.y as i64;
+            if dx.abs().max(dy.abs()) > radius {
+                continue;
+            }
+            let target = if dx.abs() >= dy.abs() {
+                CellPos { x: (food_pos.x as i64 + dx.signum()) as usize, y: food_pos.y }
+            } else {
+                CellPos { x: food_pos.x, y: (food_pos.y as i64 + dy.signum()) as usize }
+            };
+            if let Some(content) = self.grid.get_cell_content(food_pos) {
+                if target != food_pos && self.grid.is_cell_empty(target) {
+                    self.grid.clear_cell(food_pos);
+                    self.grid.set_cell(Cell { position: target, content });
+                    self.food_items[index].pos = target;
                 }
             }
-        });
+        }
     }
 
-    fn move_snake_head(&mut self, input_direction: &mut DirectionQueue) {
-        let move_dir = get_move_direction(input_direction, &self.neck_direction);
-        let dir_vector = match move_dir {
-            Direction::Up => [0, 1],
-            Direction::Down => [0, -1],
-            Direction::Left => [-1, 0],
-            Direction::Right => [1, 0]
-        };
-        let ix = (self.head_pos.x as i64) + dir_vector[0];
-        let iy = (self.head_pos.y as i64) + dir_vector[1];
-        let grid_size = GRID_SIZE as i64;
-        if ix < 0 || iy < 0 || ix >= grid_size || iy >= grid_size {
-            self.game_state = SimState::Loss;
-            return;
+    /// Returns what the snake would crash into when moving in `move_dir` and where, running into a
+    /// wall happens on the cell the head is on
+    fn get_next_head_pos(&self, move_dir: Direction) -> Result<CellPos, (EndReason, CellPos)> {
+        let head_pos = self.get_snake_neighbor(self.head_pos, move_dir).ok_or((EndReason::HitWall, self.head_pos))?;
+        if matches!(self.grid.get_cell_content(head_pos), Some(CellContent::Hazard { .. })) {
+            return Err((EndReason::HitObstacle, head_pos));
+        }
+        if self.is_position_occupied_by_snake(head_pos) && !self.has_effect(PowerUpKind::Ghost) {
+            return Err((EndReason::BitSelf, head_pos));
         }
+        Ok(head_pos)
+    }
 
-        let x = ix as usize;
-        let y = iy as usize;
+    fn end_game(&mut self, reason: EndReason, pos: CellPos) -> SimulationOver {
+        self.game_state = match reason {
+            EndReason::BoardFull => SimState::Win,
+            EndReason::Timeout => SimState::Timeout,
+            _ => SimState::Loss
+        };
+        let game_over = SimulationOver { reason, pos, tick: self.tick };
+        self.game_over = Some(game_over);
+        game_over
+    }
 
-        let head_pos = CellPos { x, y };
-        if self.is_position_occupied_by_snake(head_pos) {
-            self.game_state = SimState::Loss;
-            return;
+    fn move_snake_head(&mut self, head_pos: CellPos, move_dir: Direction) {
+        if let Some(CellContent::SnakeBody { age }) = self.grid.get_cell_content(head_pos) {
+            self.ghost_overlaps.push(SnakeBody { pos: head_pos, age });
         }
         self.grid.set_cell(Cell { position: head_pos, content: CellContent::SnakeBody { age: 1 } });
         self.neck_direction = move_dir;
         self.head_pos = head_pos;
     }
 
-    fn log_game_state_if_finished(&self) {
-        if self.game_state != SimState::Running {
-            println!("{:?}!", self.game_state);
-        }
+    fn take_eaten_food(&mut self) -> Option<FoodItem> {
+        let eaten_index = self.food_items.iter().position(| food | food.pos == self.head_pos)?;
+        Some(self.food_items.swap_remove(eaten_index))
     }
 
-    fn was_food_eaten(&self) -> bool { self.food_pos == self.head_pos }
-
     fn is_position_occupied_by_snake(&self, pos: CellPos) -> bool {
         matches!(self.grid.get_cell_content(pos), Some(CellContent::SnakeBody { .. }))
     }
 
     fn move_snake_tail(&mut self) {
-        let cur_tail = self.get_tail();
-        if self.eaten_food < cur_tail.age {
+        let mut cur_tail = self.get_tail();
+        while self.snake_length < cur_tail.age {
             let new_tail = self.get_oldest_tail_neighbor();
-            self.grid.clear_cell(cur_tail.pos);
+            self.remove_tail_segment(cur_tail);
             self.tail_pos = new_tail;
+            cur_tail = self.get_tail();
         }
     }
 
+    /// The tail may be a segment hidden under a newer one, in that case the cell stays occupied
+    fn remove_tail_segment(&mut self, tail: SnakeBody) {
+        let overlap_index = self.ghost_overlaps
+            .iter()
+            .position(| segment | segment.pos == tail.pos && segment.age == tail.age);
+        match overlap_index {
+            Some(index) => { self.ghost_overlaps.swap_remove(index); },
+            None => self.grid.clear_cell(tail.pos)
+        }
+    }
+
+    /// The cell a snake segment at `pos` moves into when going in `dir`
+    ///
+    /// Going into a portal leads to the cell right after its other end, keeping the direction.
+    /// `None` if that cell is outside the grid, is another portal or is `pos` itself.
+    fn get_snake_neighbor(&self, pos: CellPos, dir: Direction) -> Option<CellPos> {
+        let next_pos = pos.step(dir)?;
+        let Some(CellContent::Portal { pair_id }) = self.grid.get_cell_content(next_pos) else {
+            return Some(next_pos);
+        };
+        let exit_pos = self.portals[pair_id].get_other_end(next_pos).step(dir)?;
+        match self.grid.get_cell_content(exit_pos) {
+            Some(CellContent::Portal { .. }) => None,
+            _ if exit_pos == pos => None,
+            _ => Some(exit_pos)
+        }
+    }
+
+    /// Body segments on both sides of a portal are linked even though they are not grid adjacent
     fn get_oldest_tail_neighbor(&self) -> CellPos {
-        let oldest_neighbor: Option<(CellPos, Sze)> = self.tail_pos
-            .get_neighbors()
+        let oldest_neighbor: Option<(CellPos, Sze)> = DIRECTIONS
             .iter()
-            .filter_map(| &cell_pos | {
-                if let Some(CellContent::SnakeBody { age }) = self.grid.get_cell_content(cell_pos) {
-                    Some((cell_pos, age))
-                } else {
-                    None
-                }
-            })
+            .filter_map(| &dir | self.get_snake_neighbor(self.tail_pos, dir))
+            .filter_map(| cell_pos | self.get_oldest_segment_age(cell_pos).map(| age | (cell_pos, age)))
             .fold(None, | prev, (cur_pos, cur_age) | {
                 match prev {
                     Some((_, prev_age)) => {
@@ -204,60 +495,460 @@ impl Sim {
         oldest_neighbor.unwrap().0
     }
 
+    /// Segments hidden by the ghost effect are always older than the one stored in the grid
+    fn get_oldest_segment_age(&self, pos: CellPos) -> Option<Sze> {
+        let grid_age = match self.grid.get_cell_content(pos) {
+            Some(CellContent::SnakeBody { age }) => Some(age),
+            _ => None
+        };
+        self.ghost_overlaps
+            .iter()
+            .filter(| segment | segment.pos == pos)
+            .map(| segment | segment.age)
+            .max()
+            .or(grid_age)
+    }
+
     fn get_tail(&self) -> SnakeBody {
-        if let Some(CellContent::SnakeBody { age }) = self.grid.get_cell_content(self.tail_pos) {
+        if let Some(age) = self.get_oldest_segment_age(self.tail_pos) {
             SnakeBody { pos: self.tail_pos, age }
         } else {
             panic!()
         }
     }
 
+    /// Portals from the level that would land on the snake are skipped
+    fn place_portals(&mut self) {
+        self.portals.clear();
+        let level_portals = self.rules.level.as_ref().map(| level | level.portals.clone()).unwrap_or_default();
+        for pair in level_portals {
+            if self.grid.is_cell_empty(pair.a) && self.grid.is_cell_empty(pair.b) && pair.a != pair.b {
+                self.add_portal_pair(pair);
+            }
+        }
+        for _ in 0..self.rules.random_portal_pairs {
+            let Some(a) = self.grid.get_random_empty_cell(&mut self.rng) else { break };
+            self.grid.set_cell(Cell { position: a, content: CellContent::Portal { pair_id: self.portals.len() } });
+            let Some(b) = self.grid.get_random_empty_cell(&mut self.rng) else {
+                self.grid.clear_cell(a);
+                break;
+            };
+            self.add_portal_pair(PortalPair { a, b });
+        }
+    }
+
+    /// Level hazards that would overlap anything already on the board are skipped
+    fn place_hazards(&mut self) {
+        self.hazards.clear();
+        let mut level_hazards = vec!();
+        if let Some(level) = &self.rules.level {
+            level_hazards.extend(level.rocks.iter().map(| &pos | Hazard::Rock { pos }));
+            level_hazards.extend(level.timed_walls.iter().map(| &pos | {
+                Hazard::TimedWall { pos, is_open: false, ticks_in_state: 0 }
+            }));
+            level_hazards.extend(level.patrols.iter().map(| waypoints | {
+                Hazard::Patrol { path: get_patrol_path(waypoints), step: 0 }
+            }));
+            if !level.lava.is_empty() {
+                level_hazards.push(Hazard::Lava {
+                    cells: level.lava.clone(),
+                    ticks_until_spread: self.rules.hazard_config.lava_spread_every
+                });
+            }
+        }
+        level_hazards.into_iter().for_each(| hazard | self.add_hazard(hazard));
+
+        for _ in 0..self.rules.hazard_config.random_walls {
+            let Some(pos) = self.get_random_hazard_cell() else { break };
+            let is_open = self.rng.gen_bool(0.5);
+            let ticks_in_state = self.rng.gen_range(0..self.rules.hazard_config.wall_closed_ticks.max(1));
+            self.add_hazard(Hazard::TimedWall { pos, is_open, ticks_in_state });
+        }
+        for _ in 0..self.rules.hazard_config.random_lava {
+            let Some(pos) = self.get_random_hazard_cell() else { break };
+            let ticks_until_spread = self.rules.hazard_config.lava_spread_every;
+            self.add_hazard(Hazard::Lava { cells: vec!(pos), ticks_until_spread });
+        }
+        for _ in 0..self.rules.hazard_config.random_patrols {
+            let Some(start) = self.get_random_hazard_cell() else { break };
+            let dir = *DIRECTIONS.choose(&mut self.rng).unwrap();
+            let mut end = start;
+            for _ in 1..self.rules.hazard_config.patrol_length {
+                match end.step(dir) {
+                    Some(next) if self.grid.is_cell_empty(next) && !self.is_reserved_by_hazard(next) => end = next,
+                    _ => break
+                }
+            }
+            if end != start {
+                self.add_hazard(Hazard::Patrol { path: get_patrol_path(&[start, end]), step: 0 });
+            }
+        }
+    }
+
+    /// A random empty cell outside the row the snake starts on, so it never spawns facing a hazard,
+    /// and that no other hazard can take up later
+    fn get_random_hazard_cell(&mut self) -> Option<CellPos> {
+        for _ in 0..20 {
+            let pos = self.grid.get_random_empty_cell(&mut self.rng)?;
+            if pos.y != self.head_pos.y && !self.is_reserved_by_hazard(pos) {
+                return Some(pos);
+            }
+        }
+        None
+    }
+
+    fn is_reserved_by_hazard(&self, pos: CellPos) -> bool {
+        self.hazards.iter().any(| hazard | hazard.get_reserved_cells().contains(&pos))
+    }
+
+    fn add_hazard(&mut self, hazard: Hazard) {
+        let is_free = | pos: &CellPos | self.grid.is_cell_empty(*pos) && !self.is_reserved_by_hazard(*pos);
+        if hazard.get_cells().iter().all(is_free) {
+            hazard.place(&mut self.grid);
+            self.hazards.push(hazard);
+        }
+    }
+
+    /// Returns true if a hazard ran into the snake head
+    ///
+    /// Lava that spread may have walled off a part of the board, which then gets filled in.
+    fn step_hazards(&mut self) -> bool {
+        let lava_cell_count = self.get_lava_cell_count();
+        let mut is_head_hit = false;
+        for hazard in self.hazards.iter_mut() {
+            is_head_hit |= hazard.step(&mut self.grid, self.head_pos, &self.rules.hazard_config, &mut self.rng);
+        }
+        if self.get_lava_cell_count() > lava_cell_count {
+            self.fill_sealed_pockets();
+        }
+        is_head_hit
+    }
+
+    fn get_lava_cell_count(&self) -> usize {
+        self.hazards
+            .iter()
+            .map(| hazard | match hazard {
+                Hazard::Lava { cells, .. } => cells.len(),
+                _ => 0
+            })
+            .sum()
+    }
+
+    fn add_portal_pair(&mut self, pair: PortalPair) {
+        let content = CellContent::Portal { pair_id: self.portals.len() };
+        self.grid.set_cell(Cell { position: pair.a, content });
+        self.grid.set_cell(Cell { position: pair.b, content });
+        self.portals.push(pair);
+    }
+
+    /// Food spawns on the level's food zones while any of them is empty, anywhere else otherwise
+    fn get_random_food_cell(&mut self) -> Option<CellPos> {
+        let empty_zone_cells: Vec<CellPos> = self.rules.level
+            .as_ref()
+            .map(| level | level.food_zones.iter().copied().filter(| &pos | self.grid.is_cell_empty(pos)).collect())
+            .unwrap_or_default();
+        empty_zone_cells.choose(&mut self.rng).copied().or_else(|| self.grid.get_random_empty_cell(&mut self.rng))
+    }
+
+    /// Fills the empty cells the head can never get to with rocks, so the board can still fill up
+    ///
+    /// The food and power-up walled in with them are dropped first, and so are the walls and blocks
+    /// that can reach into them.
+    fn fill_sealed_pockets(&mut self) {
+        let is_reachable = self.get_reachable_cells();
+        let is_sealed = | pos: &CellPos | !is_reachable[pos.x][pos.y];
+        let (sealed_hazards, hazards): (Vec<Hazard>, Vec<Hazard>) = std::mem::take(&mut self.hazards)
+            .into_iter()
+            .partition(| hazard | {
+                matches!(hazard, Hazard::Patrol { .. } | Hazard::TimedWall { .. })
+                    && hazard.get_reserved_cells().iter().any(is_sealed)
+            });
+        self.hazards = hazards;
+        for pos in sealed_hazards.iter().flat_map(| hazard | hazard.get_cells()) {
+            // Lava may have spread into an open wall
+            if matches!(self.grid.get_cell_content(pos), Some(CellContent::Hazard { kind: HazardKind::Block | HazardKind::Wall })) {
+                self.grid.clear_cell(pos);
+            }
+        }
+        let (sealed_food, food_items): (Vec<FoodItem>, Vec<FoodItem>) = self.food_items
+            .iter()
+            .partition(| food | is_sealed(&food.pos));
+        sealed_food.iter().for_each(| food | self.grid.clear_cell(food.pos));
+        self.food_items = food_items;
+        if let Some(power_up) = self.power_up.filter(| power_up | is_sealed(&power_up.pos)) {
+            self.grid.clear_cell(power_up.pos);
+            self.power_up = None;
+        }
+        for x in 0..GRID_SIZE {
+            for y in 0..GRID_SIZE {
+                let pos = CellPos { x, y };
+                if is_sealed(&pos) && self.grid.is_cell_empty(pos) {
+                    self.add_hazard(Hazard::Rock { pos });
+                }
+            }
+        }
+    }
+
+    /// Which cells the head can get to, going through portals
+    ///
+    /// Only rocks and lava are in the way, the body, walls and blocks all move on sooner or later.
+    fn get_reachable_cells(&self) -> [[bool; GRID_SIZE]; GRID_SIZE] {
+        let mut is_reachable = [[false; GRID_SIZE]; GRID_SIZE];
+        let mut to_visit = vec!(self.head_pos);
+        is_reachable[self.head_pos.x][self.head_pos.y] = true;
+        while let Some(pos) = to_visit.pop() {
+            for &dir in DIRECTIONS.iter() {
+                let Some(next_pos) = self.get_snake_neighbor(pos, dir) else { continue };
+                let is_blocked = matches!(
+                    self.grid.get_cell_content(next_pos),
+                    Some(CellContent::Hazard { kind: HazardKind::Rock | HazardKind::Lava })
+                );
+                if !is_blocked && !is_reachable[next_pos.x][next_pos.y] {
+                    is_reachable[next_pos.x][next_pos.y] = true;
+                    to_visit.push(next_pos);
+                }
+            }
+        }
+        is_reachable
+    }
+
     fn spawn_food(&mut self) -> bool {
-        let mut rng = rand::thread_rng();
-        let mut is_food_spawned = false;
-        for _ in 1..20 {
-            let food_pos = CellPos { x: rng.gen_range(0..GRID_SIZE), y: rng.gen_range(0..GRID_SIZE) };
-            if self.grid.get_cell_content(food_pos).is_none() {
-                self.grid.set_cell(Cell { position: food_pos, content: CellContent::Food });
-                self.food_pos = food_pos;
-                is_food_spawned = true;
+        let Some(food_pos) = self.get_random_food_cell() else {
+            return false;
+        };
+        let kind = *FOOD_KINDS
+            .choose_weighted(&mut self.rng, | &kind | self.rules.food_config.get_rule(kind).spawn_weight)
+            .unwrap_or(&FoodKind::Normal);
+        self.food_items.push(FoodItem { pos: food_pos, kind });
+        self.grid.set_cell(Cell { position: food_pos, content: CellContent::Food { kind, age: 0 } });
+        true
+    }
+
+    /// Spawns regular food until there are `food_count` pieces on the board or no space is left
+    fn top_up_food(&mut self) {
+        while self.get_regular_food_count() < self.rules.food_config.food_count {
+            if !self.spawn_food() {
                 break;
             }
         }
-        if !is_food_spawned {
-            if let Some(&food_pos) = self.get_empty_cells_around_tail().choose(&mut rng) {
-                self.grid.set_cell(Cell { position: food_pos, content: CellContent::Food });
-                self.food_pos = food_pos;
-                is_food_spawned = true;
-            };
+    }
+
+    fn get_regular_food_count(&self) -> usize {
+        self.food_items.iter().filter(| food | food.kind != FoodKind::Bonus).count()
+    }
+
+    fn spawn_bonus_food_at_random(&mut self) {
+        let is_bonus_on_board = self.food_items.iter().any(| food | food.kind == FoodKind::Bonus);
+        if is_bonus_on_board || !self.rng.gen_ratio(1, self.rules.food_config.bonus_spawn_one_in) {
+            return;
         }
-        is_food_spawned
-    }
-
-    fn get_empty_cells_around_tail(&self) -> Vec<CellPos> {
-        let search_area = 10;
-        let tail_pos = &self.tail_pos;
-        let mut empty_cells = vec!();
-        let low_x = tail_pos.x - min(tail_pos.x, search_area);
-        let high_x = min(GRID_SIZE, tail_pos.x + search_area);
-        let low_y = tail_pos.y - min(tail_pos.y, search_area);
-        let high_y = min(GRID_SIZE, tail_pos.y + search_area);
-        for x in low_x..high_x {
-            for y in low_y..high_y {
-                let pos: CellPos = CellPos { x, y };
-                if self.grid.is_cell_empty(pos) { empty_cells.push(pos); }
+        if let Some(bonus_pos) = self.get_random_food_cell() {
+            let kind = FoodKind::Bonus;
+            self.food_items.push(FoodItem { pos: bonus_pos, kind });
+            self.grid.set_cell(Cell { position: bonus_pos, content: CellContent::Food { kind, age: 0 } });
+        }
+    }
+
+    fn spawn_power_up_at_random(&mut self) {
+        if self.power_up.is_some() || !self.rng.gen_ratio(1, self.rules.power_up_config.spawn_one_in) {
+            return;
+        }
+        if let Some(power_up_pos) = self.grid.get_random_empty_cell(&mut self.rng) {
+            let kind = *POWER_UP_KINDS.choose(&mut self.rng).unwrap();
+            self.power_up = Some(PowerUpItem { pos: power_up_pos, kind });
+            self.grid.set_cell(Cell { position: power_up_pos, content: CellContent::PowerUp { kind, age: 0 } });
+        }
+    }
+
+    fn expire_power_up(&mut self) {
+        if let Some(power_up) = self.power_up {
+            if let Some(CellContent::PowerUp { age, .. }) = self.grid.get_cell_content(power_up.pos) {
+                if age >= self.rules.power_up_config.lifetime {
+                    self.grid.clear_cell(power_up.pos);
+                    self.power_up = None;
+                }
             }
         }
-        empty_cells
     }
 
+    fn collect_power_up(&mut self) {
+        let Some(power_up) = self.power_up.filter(| power_up | power_up.pos == self.head_pos) else {
+            return;
+        };
+        self.power_up = None;
+        let remaining_ticks = self.rules.power_up_config.get_duration(power_up.kind);
+        match self.active_effects.iter_mut().find(| effect | effect.kind == power_up.kind) {
+            Some(effect) => effect.remaining_ticks = remaining_ticks,
+            None => self.active_effects.push(ActiveEffect { kind: power_up.kind, remaining_ticks })
+        }
+        self.replay.record_event(self.tick, ReplayEvent::PowerUpCollected(power_up.kind));
+    }
+
+    /// An effect collected with a duration of N stays active for the next N steps
+    fn tick_effects(&mut self) {
+        let tick = self.tick;
+        let replay = &mut self.replay;
+        self.active_effects.retain(| effect | {
+            if effect.remaining_ticks == 0 {
+                replay.record_event(tick, ReplayEvent::EffectExpired(effect.kind));
+            }
+            effect.remaining_ticks > 0
+        });
+        self.active_effects.iter_mut().for_each(| effect | effect.remaining_ticks -= 1);
+    }
+
+    fn consume_effect(&mut self, kind: PowerUpKind) -> bool {
+        let effect_index = self.active_effects.iter().position(| effect | effect.kind == kind);
+        if let Some(index) = effect_index {
+            self.active_effects.remove(index);
+        }
+        effect_index.is_some()
+    }
+
+    pub fn has_effect(&self, kind: PowerUpKind) -> bool {
+        self.active_effects.iter().any(| effect | effect.kind == kind)
+    }
+
+    pub fn get_active_effects(&self) -> &[ActiveEffect] { &self.active_effects }
+
+    pub fn get_replay(&self) -> &Replay { &self.replay }
+
+    pub fn get_score(&self) -> Sze { self.score }
+
+    pub fn get_tick(&self) -> Sze { self.tick }
+
+    pub fn get_combo(&self) -> &Combo { &self.combo }
+
+    pub fn is_boosting(&self) -> bool { self.is_boosting }
+
+    pub fn get_snake_length(&self) -> Sze { self.snake_length }
+
+    /// Counts the pieces the snake started with too
+    pub fn get_eaten_food(&self) -> Sze { self.eaten_food }
+
+    /// `None` while the game is running
+    pub fn get_game_over(&self) -> Option<SimulationOver> { self.game_over }
+
+    pub fn is_score_ranked(&self) -> bool { !self.rewind_used }
+
+    pub fn take_snapshot(&mut self) -> SimSnapshot {
+        let replay = std::mem::take(&mut self.replay);
+        let snapshot = SimSnapshot {
+            sim: self.clone(),
+            seed: replay.seed,
+            replay_moves: replay.moves.len(),
+            replay_events: replay.events.len()
+        };
+        self.replay = replay;
+        snapshot
+    }
+
+    /// Goes back to `snapshot`, the replay is cut down to the moves made up to that point
+    pub fn restore_snapshot(&mut self, snapshot: SimSnapshot) {
+        let mut replay = std::mem::take(&mut self.replay);
+        replay.moves.truncate(snapshot.replay_moves);
+        replay.events.truncate(snapshot.replay_events);
+        *self = snapshot.sim;
+        self.replay = replay;
+        self.rewind_used = true;
+    }
+
+    /// Checks what a deserialized `Sim` relies on but the format can't express, like the body
+    /// being connected from tail to head or patrols having a path to walk
+    pub fn is_consistent(&self) -> bool {
+        let is_snake_at = | pos: CellPos | self.is_position_occupied_by_snake(pos);
+        let is_food_at = | pos: CellPos | matches!(self.grid.get_cell_content(pos), Some(CellContent::Food { .. }));
+        let is_hazard_at = | pos: CellPos, kind: HazardKind | self.grid.get_cell_content(pos) == Some(CellContent::Hazard { kind });
+        let is_hazard_valid = | hazard: &Hazard | match hazard {
+            Hazard::Patrol { path, step } => path.get(*step).is_some_and(| &pos | is_hazard_at(pos, HazardKind::Block)),
+            Hazard::TimedWall { pos, is_open, .. } => *is_open || is_hazard_at(*pos, HazardKind::Wall),
+            Hazard::Lava { cells, .. } => !cells.is_empty() && cells.iter().all(| &pos | is_hazard_at(pos, HazardKind::Lava)),
+            Hazard::Rock { pos } => is_hazard_at(*pos, HazardKind::Rock)
+        };
+        let is_portal_at = | pos: CellPos, pair_id: usize | self.grid.get_cell_content(pos) == Some(CellContent::Portal { pair_id });
+        let are_portals_valid = self.portals
+            .iter()
+            .enumerate()
+            .all(| (pair_id, pair) | pair.a != pair.b && is_portal_at(pair.a, pair_id) && is_portal_at(pair.b, pair_id));
+        let are_portal_ids_valid = self.grid
+            .get_occupied_cells()
+            .iter()
+            .all(| cell | !matches!(cell.content, CellContent::Portal { pair_id } if pair_id >= self.portals.len()));
+        let are_level_patrols_valid = self.rules.level
+            .iter()
+            .flat_map(| level | level.patrols.iter())
+            .all(| waypoints | !waypoints.is_empty());
+        is_snake_at(self.head_pos)
+            && is_snake_at(self.tail_pos)
+            && are_portals_valid
+            && are_portal_ids_valid
+            && self.get_body_segment_count().is_some_and(| segment_count | segment_count <= self.snake_length)
+            && self.food_items.iter().all(| food | is_food_at(food.pos))
+            && self.hazards.iter().all(is_hazard_valid)
+            && are_level_patrols_valid
+            && self.rules.food_config.food_count > 0
+            && self.rules.food_config.bonus_spawn_one_in > 0
+            && self.rules.power_up_config.spawn_one_in > 0
+    }
+
+    /// Follows the body from the tail to the head one age at a time, `None` if it breaks off
+    /// before reaching the head
+    fn get_body_segment_count(&self) -> Option<Sze> {
+        let has_segment = | pos: CellPos, age: Sze | {
+            self.grid.get_cell_content(pos) == Some(CellContent::SnakeBody { age })
+                || self.ghost_overlaps.iter().any(| segment | segment.pos == pos && segment.age == age)
+        };
+        let segment_count = self.get_oldest_segment_age(self.tail_pos)?;
+        let mut pos = self.tail_pos;
+        for age in (1..segment_count).rev() {
+            pos = DIRECTIONS
+                .iter()
+                .filter_map(| &dir | self.get_snake_neighbor(pos, dir))
+                .find(| &next_pos | has_segment(next_pos, age))?;
+        }
+        (pos == self.head_pos).then_some(segment_count)
+    }
+
+    pub fn get_remaining_ticks(&self) -> Option<Sze> { self.remaining_ticks }
+
     pub fn get_occupied_cells(&self) -> Vec<Cell> { self.grid.get_occupied_cells() }
 
+    /// Positions of the snake, for drawing it next to another game
+    pub fn get_snake_cells(&self) -> Vec<CellPos> {
+        self.get_occupied_cells()
+            .into_iter()
+            .filter(| cell | matches!(cell.content, CellContent::SnakeBody { .. }))
+            .map(| cell | cell.position)
+            .collect()
+    }
+
+    /// Compared between versus peers to catch simulations that went apart
+    pub fn get_grid_hash(&self) -> u64 { self.grid.get_state_hash() }
+
     pub fn get_head_position(&self) -> CellPos { self.head_pos }
 
     pub fn is_game_running(&self) -> bool { self.game_state == SimState::Running }
 }
 
+impl EndReason {
+    pub fn is_win(&self) -> bool { *self == Self::BoardFull }
+
+    pub fn get_message_id(&self) -> &'static str {
+        match self {
+            Self::HitWall => "end-hit-wall",
+            Self::BitSelf => "end-bit-self",
+            Self::HitObstacle => "end-hit-obstacle",
+            Self::Timeout => "end-timeout",
+            Self::BoardFull => "end-board-full"
+        }
+    }
+}
+
+impl SimSnapshot {
+    /// True if the snapshot was taken earlier in the game `sim` is playing
+    pub fn precedes(&self, sim: &Sim) -> bool { self.seed == sim.replay.seed && self.sim.tick < sim.tick }
+}
+
 fn get_move_direction(dir_queue: &mut DirectionQueue, neck_dir: &Direction) -> Direction {
     while let Some(input_direction) = dir_queue.pop() {
         if input_direction != *neck_dir && input_direction != neck_dir.opposite() {
@@ -267,7 +958,8 @@ fn get_move_direction(dir_queue: &mut DirectionQueue, neck_dir: &Direction) -> D
     *neck_dir
 }
 
-fn get_random_head_pos(rng: &mut ThreadRng) -> CellPos {
+/// Leaves room for the whole body to the left of the head and at least half the board ahead of it
+pub fn get_random_head_pos<R: Rng>(rng: &mut R) -> CellPos {
     let x = rng.gen_range(START_SNAKE_LENGHT..(GRID_SIZE / 2));
     let y = rng.gen_range(0..GRID_SIZE);
     CellPos { x, y }
@@ -278,9 +970,8 @@ fn get_random_head_pos(rng: &mut ThreadRng) -> CellPos {
 /// Meaning that grid should be empty
 ///
 /// See `Grid::new_empty_grid` and `Grid::clear_grid`
-fn get_random_sim_start(grid: &mut Grid) -> GameInitialization {
-    let mut rng = rand::thread_rng();
-    let head_pos = get_random_head_pos(&mut rng);
+fn get_random_sim_start<R: Rng>(grid: &mut Grid, rng: &mut R, start: Option<CellPos>) -> GameInitialization {
+    let head_pos = start.unwrap_or_else(|| get_random_head_pos(rng));
     let tail_pos = CellPos {
         x: head_pos.x + 1 - START_SNAKE_LENGHT,
         y: head_pos.y
@@ -291,21 +982,94 @@ fn get_random_sim_start(grid: &mut Grid) -> GameInitialization {
             content: CellContent::SnakeBody { age: (START_SNAKE_LENGHT - offset) as Sze }
         });
     }
-    let food_pos = CellPos {
-        x: (GRID_SIZE + head_pos.x) / 2,
-        y: rng.gen_range(0..GRID_SIZE)
-    };
-    grid.set_cell(Cell { position: food_pos, content: CellContent::Food });
     let score_multiplier = 1;
     let eaten_food = START_SNAKE_LENGHT as Sze;
     GameInitialization {
         eaten_food,
+        snake_length: eaten_food,
         score: eaten_food * score_multiplier * SCORE_BASE,
         score_multiplier,
         neck_direction: Direction::Right,
         game_state: SimState::Running,
         head_pos,
-        tail_pos,
-        food_pos
+        tail_pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::generate_level;
+
+    /// Cells the head can get to, only rocks and lava never get out of the way
+    fn get_reachable_cells(sim: &Sim) -> Vec<CellPos> {
+        let mut reachable = vec!(sim.head_pos);
+        let mut index = 0;
+        while let Some(&pos) = reachable.get(index) {
+            for &dir in DIRECTIONS.iter() {
+                let Some(next_pos) = sim.get_snake_neighbor(pos, dir) else { continue };
+                let is_blocked = matches!(
+                    sim.grid.get_cell_content(next_pos),
+                    Some(CellContent::Hazard { kind: HazardKind::Rock | HazardKind::Lava })
+                );
+                if !is_blocked && !reachable.contains(&next_pos) {
+                    reachable.push(next_pos);
+                }
+            }
+            index += 1;
+        }
+        reachable
+    }
+
+    #[test]
+    fn empty_cells_are_never_walled_in() {
+        let mut sim = Sim::new_simulation();
+        for seed in 0..300 {
+            let level = (seed % 2 == 0).then(|| generate_level(seed, (seed % 11) as u32));
+            sim.set_rules(GameRules {
+                hazard_config: HazardConfig { random_lava: 3, lava_spread_every: 1, lava_max_cells: 40, ..HazardConfig::with_random_hazards() },
+                random_portal_pairs: 2,
+                level,
+                ..GameRules::default()
+            });
+            sim.reset_with_seed(seed);
+            assert!(!sim.food_items.is_empty(), "no food on seed {}", seed);
+            for spread in 0..40 {
+                let reachable = get_reachable_cells(&sim);
+                let empty_cells = (0..GRID_SIZE)
+                    .flat_map(| x | (0..GRID_SIZE).map(move | y | CellPos { x, y }))
+                    .filter(| &pos | sim.grid.is_cell_empty(pos));
+                for pos in empty_cells {
+                    assert!(reachable.contains(&pos), "{:?} is walled in on seed {} after {} spreads", pos, seed, spread);
+                }
+                for food in sim.food_items.iter() {
+                    assert!(reachable.contains(&food.pos), "food at {:?} is walled in on seed {}", food.pos, seed);
+                }
+                sim.step_hazards();
+            }
+        }
+    }
+
+    #[test]
+    fn random_hazards_never_share_a_cell() {
+        let mut sim = Sim::new_simulation();
+        for seed in 0..300 {
+            sim.set_rules(GameRules {
+                hazard_config: HazardConfig { random_patrols: 6, random_walls: 12, random_lava: 4, ..HazardConfig::default() },
+                ..GameRules::default()
+            });
+            sim.reset_with_seed(seed);
+            let mut taken_cells = vec!();
+            for hazard in sim.hazards.iter() {
+                // A patrol goes back over its own path
+                let mut cells = hazard.get_reserved_cells();
+                cells.sort_by_key(| pos | (pos.x, pos.y));
+                cells.dedup();
+                for pos in cells {
+                    assert!(!taken_cells.contains(&pos), "two hazards share {:?} on seed {}", pos, seed);
+                    taken_cells.push(pos);
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,422 @@
+use std::{
+    io,
+    net::{TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant}
+};
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use serde::{Serialize, Deserialize};
+use tungstenite::{
+    Message,
+    WebSocket,
+    HandshakeError,
+    handshake::{MidHandshake, server::{ServerHandshake, NoCallback}}
+};
+
+use crate::{
+    Sze,
+    FoodEaten,
+    SimulationOver,
+    cell::{Cell, CellPos, CellContent},
+    food::FoodKind,
+    grid::GRID_SIZE,
+    ghost::ReplayCursor,
+    locale::{Locale, Language},
+    replay::Replay,
+    simulation::{Sim, StepEvents, EndReason},
+    timers::StepTimers,
+    render::{render_spectated_game, spawn_background, get_score_transform, get_split_transform}
+};
+
+pub const DEFAULT_SPECTATOR_PORT: u16 = 9001;
+/// How often the headless server steps and streams
+const HEADLESS_FRAME_SECONDS: f32 = 1.0 / 60.0;
+/// The final board stays up this long before the replay starts over
+const REPLAY_RESTART_SECONDS: u64 = 5;
+
+/// What a viewer needs besides the cells to draw the board
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardStatus {
+    pub seed: u64,
+    pub tick: Sze,
+    pub score: Sze,
+    pub head: CellPos,
+    pub game_over: Option<SimulationOver>
+}
+
+/// Sent to viewers as RON in text messages
+#[derive(Serialize, Deserialize)]
+pub enum StreamMessage {
+    /// The whole board, for viewers that just joined and whenever a game starts or is rewound
+    Snapshot { status: BoardStatus, cells: Vec<Cell> },
+    /// The cells that changed since the last message, `cleared` ones are empty now
+    Delta { status: BoardStatus, changed: Vec<Cell>, cleared: Vec<CellPos> },
+    FoodEaten { food_kind: FoodKind, new_score: Sze },
+    GameOver(SimulationOver)
+}
+
+/// Board contents indexed by `x * GRID_SIZE + y`
+type CellContents = Vec<Option<CellContent>>;
+
+type PendingViewer = MidHandshake<ServerHandshake<TcpStream, NoCallback>>;
+
+/// Streams a game to any number of WebSocket viewers
+///
+/// Viewers get a snapshot of the board when they join and when a game starts, then only the
+/// cells that changed since the last message.
+#[derive(Resource)]
+pub struct SpectatorServer {
+    listener: TcpListener,
+    /// Viewers whose handshake is still waiting on the network
+    pending: Vec<PendingViewer>,
+    viewers: Vec<WebSocket<TcpStream>>,
+    /// What was last sent, deltas are taken against it
+    status: Option<BoardStatus>,
+    contents: CellContents
+}
+
+/// A connection to a `SpectatorServer` and the board put back together from its messages
+#[derive(Resource)]
+pub struct SpectatorClient {
+    socket: WebSocket<TcpStream>,
+    is_connected: bool,
+    status: Option<BoardStatus>,
+    contents: CellContents,
+    last_event: Option<ViewerEvent>
+}
+
+/// The last thing that happened in the watched game, shown under the board
+enum ViewerEvent {
+    FoodEaten { food_kind: FoodKind, new_score: Sze },
+    GameOver(EndReason)
+}
+
+/// Watches a game streamed by `--spectate`, drawn like the game itself
+pub struct ViewerPlugin {
+    pub language: &'static Language
+}
+
+/// Hands the plugin's language to `setup_viewer`
+#[derive(Resource)]
+struct ViewerLanguage(&'static Language);
+
+#[derive(Resource)]
+struct ViewerTexts {
+    score: Entity,
+    status: Entity
+}
+
+impl SpectatorServer {
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+        Ok(SpectatorServer {
+            listener,
+            pending: vec!(),
+            viewers: vec!(),
+            status: None,
+            contents: vec!(None; GRID_SIZE * GRID_SIZE)
+        })
+    }
+
+    pub fn get_viewer_count(&self) -> usize { self.viewers.len() }
+
+    /// Takes in new viewers and sends them the whole board, drops the ones that left
+    pub fn accept_viewers(&mut self, sim: &Sim) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            // Accepted sockets don't take on non-blocking everywhere
+            if stream.set_nonblocking(true).is_ok() {
+                self.continue_handshake(tungstenite::accept(stream), sim);
+            }
+        }
+        for pending in std::mem::take(&mut self.pending) {
+            self.continue_handshake(pending.handshake(), sim);
+        }
+        // Viewers don't send anything, reading answers their pings and notices when they close
+        self.viewers.retain_mut(| viewer | loop {
+            match viewer.read() {
+                Ok(_) => {},
+                Err(tungstenite::Error::Io(error)) if error.kind() == io::ErrorKind::WouldBlock => break true,
+                Err(_) => break false
+            }
+        });
+    }
+
+    /// Sends what changed since the last call, the whole board when a new game started
+    pub fn send_board(&mut self, sim: &Sim) {
+        let status = get_board_status(sim);
+        let is_new_game = self.status.as_ref().map_or(true, | last | last.seed != status.seed || last.tick > status.tick);
+        if self.status.as_ref() == Some(&status) {
+            return;
+        }
+        let cells = sim.get_occupied_cells();
+        let contents = get_cell_contents(&cells);
+        let message = if is_new_game {
+            StreamMessage::Snapshot { status: status.clone(), cells }
+        } else {
+            let mut changed = vec!();
+            let mut cleared = vec!();
+            for (index, (last, content)) in self.contents.iter().zip(&contents).enumerate() {
+                let position = CellPos { x: index / GRID_SIZE, y: index % GRID_SIZE };
+                match content {
+                    Some(content) if last.as_ref() != Some(content) => changed.push(Cell { position, content: *content }),
+                    None if last.is_some() => cleared.push(position),
+                    _ => {}
+                }
+            }
+            StreamMessage::Delta { status: status.clone(), changed, cleared }
+        };
+        self.status = Some(status);
+        self.contents = contents;
+        self.broadcast(&message);
+    }
+
+    pub fn send_food_eaten(&mut self, event: &FoodEaten) {
+        self.broadcast(&StreamMessage::FoodEaten { food_kind: event.food_kind, new_score: event.new_score });
+    }
+
+    pub fn send_game_over(&mut self, event: SimulationOver) {
+        self.broadcast(&StreamMessage::GameOver(event));
+    }
+
+    fn continue_handshake(&mut self, result: Result<WebSocket<TcpStream>, HandshakeError<ServerHandshake<TcpStream, NoCallback>>>, sim: &Sim) {
+        match result {
+            Ok(mut viewer) => {
+                let snapshot = StreamMessage::Snapshot { status: get_board_status(sim), cells: sim.get_occupied_cells() };
+                if let Some(text) = encode(&snapshot) {
+                    if send_text(&mut viewer, text) {
+                        self.viewers.push(viewer);
+                    }
+                }
+            },
+            Err(HandshakeError::Interrupted(pending)) => self.pending.push(pending),
+            Err(HandshakeError::Failure(error)) => eprintln!("Turning away a viewer: {}", error)
+        }
+    }
+
+    /// Viewers that can't be written to anymore are dropped
+    fn broadcast(&mut self, message: &StreamMessage) {
+        if self.viewers.is_empty() {
+            return;
+        }
+        if let Some(text) = encode(message) {
+            self.viewers.retain_mut(| viewer | send_text(viewer, text.clone()));
+        }
+    }
+}
+
+impl SpectatorClient {
+    pub fn connect(address: &str) -> io::Result<Self> {
+        let stream = TcpStream::connect(address)?;
+        let (socket, _) = tungstenite::client(format!("ws://{}/", address), stream)
+            .map_err(| error | io::Error::other(error.to_string()))?;
+        socket.get_ref().set_nonblocking(true)?;
+        Ok(SpectatorClient {
+            socket,
+            is_connected: true,
+            status: None,
+            contents: vec!(None; GRID_SIZE * GRID_SIZE),
+            last_event: None
+        })
+    }
+
+    /// Applies every message that came in since the last call
+    pub fn receive(&mut self) {
+        while self.is_connected {
+            match self.socket.read() {
+                Ok(Message::Text(text)) => match ron::from_str(&text) {
+                    Ok(message) => self.apply(message),
+                    Err(error) => eprintln!("Ignoring an unreadable message: {}", error)
+                },
+                Ok(_) => {},
+                Err(tungstenite::Error::Io(error)) if error.kind() == io::ErrorKind::WouldBlock => return,
+                Err(_) => self.is_connected = false
+            }
+        }
+    }
+
+    pub fn get_status(&self) -> Option<&BoardStatus> { self.status.as_ref() }
+
+    pub fn get_cells(&self) -> Vec<Cell> {
+        self.contents.iter()
+            .enumerate()
+            .filter_map(| (index, content) | content.map(| content | Cell {
+                position: CellPos { x: index / GRID_SIZE, y: index % GRID_SIZE },
+                content
+            }))
+            .collect()
+    }
+
+    pub fn get_description(&self, locale: &Locale) -> String {
+        match (self.is_connected, &self.status, &self.last_event) {
+            (false, _, _) => locale.get("viewer-stream-closed"),
+            (true, None, _) => locale.get("viewer-waiting"),
+            (true, Some(_), None) => String::new(),
+            (true, Some(_), Some(ViewerEvent::FoodEaten { food_kind, new_score })) => locale.format("viewer-food-eaten", &[
+                ("food", locale.get(food_kind.get_message_id()).into()),
+                ("score", (*new_score).into())
+            ]),
+            (true, Some(_), Some(ViewerEvent::GameOver(reason))) => locale.get(reason.get_message_id())
+        }
+    }
+
+    fn apply(&mut self, message: StreamMessage) {
+        match message {
+            StreamMessage::Snapshot { status, cells } => {
+                if self.status.as_ref().map_or(true, | last | last.seed != status.seed || last.tick > status.tick) {
+                    self.last_event = None;
+                }
+                self.contents = get_cell_contents(&cells);
+                self.status = Some(status);
+            },
+            // A delta only makes sense on top of a snapshot, which is always sent first
+            StreamMessage::Delta { status, changed, cleared } => {
+                if self.status.is_some() {
+                    for cell in changed {
+                        self.contents[cell.position.x * GRID_SIZE + cell.position.y] = Some(cell.content);
+                    }
+                    for position in cleared {
+                        self.contents[position.x * GRID_SIZE + position.y] = None;
+                    }
+                    self.status = Some(status);
+                }
+            },
+            StreamMessage::FoodEaten { food_kind, new_score } => {
+                self.last_event = Some(ViewerEvent::FoodEaten { food_kind, new_score });
+            },
+            StreamMessage::GameOver(event) => self.last_event = Some(ViewerEvent::GameOver(event.reason))
+        }
+    }
+}
+
+impl Plugin for ViewerPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .insert_resource(ViewerLanguage(self.language))
+            .add_systems(Startup, setup_viewer)
+            .add_systems(Update, (
+                receive_stream,
+                update_viewer_texts.after(receive_stream),
+                render_spectated_game.after(receive_stream)
+            ));
+    }
+}
+
+/// Plays `replay` over and over without a window and streams it, for showing a recorded game
+pub fn serve_replay(server: &mut SpectatorServer, replay: &Replay) -> ! {
+    let frame = Duration::from_secs_f32(HEADLESS_FRAME_SECONDS);
+    let mut sim = Sim::new_simulation();
+    loop {
+        sim.set_rules(replay.rules.clone());
+        sim.reset_with_seed(replay.seed);
+        let mut cursor = ReplayCursor::default();
+        let mut step_timers = StepTimers::default();
+        step_timers.reset_tick_speed(sim.get_difficulty());
+        let mut last_frame = Instant::now();
+        while sim.is_game_running() {
+            thread::sleep(frame);
+            let steps = step_timers.advance(last_frame.elapsed(), false);
+            last_frame = Instant::now();
+            for _ in 0..steps {
+                if !sim.is_game_running() {
+                    break;
+                }
+                let StepEvents { food_eaten, game_over } = cursor.step(&mut sim, replay);
+                if let Some(event) = food_eaten {
+                    step_timers.on_food_eaten(&event);
+                    server.send_food_eaten(&event);
+                }
+                if let Some(event) = game_over {
+                    server.send_game_over(event);
+                }
+            }
+            server.accept_viewers(&sim);
+            server.send_board(&sim);
+        }
+        let restart_at = Instant::now() + Duration::from_secs(REPLAY_RESTART_SECONDS);
+        while Instant::now() < restart_at {
+            thread::sleep(frame);
+            server.accept_viewers(&sim);
+        }
+    }
+}
+
+fn get_board_status(sim: &Sim) -> BoardStatus {
+    BoardStatus {
+        seed: sim.get_replay().seed,
+        tick: sim.get_tick(),
+        score: sim.get_score(),
+        head: sim.get_head_position(),
+        game_over: sim.get_game_over()
+    }
+}
+
+fn get_cell_contents(cells: &[Cell]) -> CellContents {
+    let mut contents = vec!(None; GRID_SIZE * GRID_SIZE);
+    for cell in cells {
+        contents[cell.position.x * GRID_SIZE + cell.position.y] = Some(cell.content);
+    }
+    contents
+}
+
+fn encode(message: &StreamMessage) -> Option<String> {
+    ron::to_string(message).map_err(| error | eprintln!("Couldn't encode a stream message: {}", error)).ok()
+}
+
+/// False once the viewer is gone, a message that doesn't fit into the socket right away waits in
+/// the write buffer for the next one
+fn send_text(viewer: &mut WebSocket<TcpStream>, text: String) -> bool {
+    match viewer.send(Message::Text(text)) {
+        Ok(()) => true,
+        Err(tungstenite::Error::Io(error)) => error.kind() == io::ErrorKind::WouldBlock,
+        Err(_) => false
+    }
+}
+
+fn setup_viewer(
+    asset_server: Res<AssetServer>,
+    language: Res<ViewerLanguage>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut fonts: ResMut<Assets<Font>>,
+) {
+    commands.spawn(Camera2dBundle::default());
+    spawn_background(&mut commands, &mut meshes, &mut materials);
+    let locale = Locale::new(language.0, &asset_server, &mut fonts);
+    let font = locale.get_font();
+    let mut spawn_text = | font_size: f32, transform: Transform, anchor: Anchor | commands.spawn(Text2dBundle {
+        text: Text::from_section(String::new(), TextStyle { font: font.clone(), font_size, color: Color::BLACK }),
+        transform,
+        text_anchor: anchor,
+        ..Default::default()
+    }).id();
+    let score = spawn_text(30.0, get_score_transform(), Anchor::BottomRight);
+    let status = spawn_text(24.0, get_split_transform(), Anchor::TopCenter);
+    commands.insert_resource(ViewerTexts { score, status });
+    commands.insert_resource(locale);
+}
+
+fn receive_stream(mut client: ResMut<SpectatorClient>) {
+    client.receive();
+}
+
+fn update_viewer_texts(
+    client: Res<SpectatorClient>,
+    locale: Res<Locale>,
+    texts: Option<Res<ViewerTexts>>,
+    mut text_query: Query<&mut Text>
+) {
+    let Some(texts) = texts else {
+        return;
+    };
+    if client.is_changed() {
+        if let Ok(mut text) = text_query.get_mut(texts.score) {
+            text.sections[0].value = client.get_status().map(| status | status.score.to_string()).unwrap_or_default();
+        }
+        if let Ok(mut text) = text_query.get_mut(texts.status) {
+            text.sections[0].value = client.get_description(&locale);
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::{collections::BTreeMap, fs, io, time::Duration};
+use bevy::prelude::{Resource, Color};
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    Sze,
+    SimulationOver,
+    simulation::{Sim, EndReason},
+    difficulty::{Difficulty, DIFFICULTIES},
+    locale::Locale
+};
+
+const STATS_DIR: &str = "saves";
+const STATS_PATH: &str = "saves/stats.ron";
+/// Games shown on the recent scores chart
+pub const RECENT_SCORES_LEN: usize = 20;
+
+/// Every way a game can end, in the order the outcome chart stacks them
+pub const OUTCOMES: [Outcome; 5] = [
+    Outcome::Win,
+    Outcome::Wall,
+    Outcome::OwnBody,
+    Outcome::Obstacle,
+    Outcome::Timeout
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome { Win, Wall, OwnBody, Obstacle, Timeout }
+
+/// Totals over a number of games, times are only counted while the game runs
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatsRecord {
+    pub games_played: Sze,
+    pub wins: Sze,
+    pub losses_to_wall: Sze,
+    pub losses_to_own_body: Sze,
+    pub losses_to_obstacle: Sze,
+    pub timeouts: Sze,
+    pub total_food: Sze,
+    pub longest_snake: Sze,
+    pub best_score: Sze,
+    /// Only difficulties that were played have an entry
+    pub best_scores: BTreeMap<Difficulty, Sze>,
+    pub total_survival_ticks: u64,
+    pub time_boosting: Duration,
+    pub time_played: Duration,
+    /// Oldest first, at most `RECENT_SCORES_LEN`
+    pub recent_scores: Vec<Sze>
+}
+
+/// Stats since the game was launched and since the first launch, only the lifetime ones are saved
+///
+/// Rewound games are left out of the game results, the food eaten and time spent in them still counts.
+#[derive(Resource, Default)]
+pub struct Stats {
+    pub session: StatsRecord,
+    pub lifetime: StatsRecord
+}
+
+impl Outcome {
+    pub fn get_message_id(&self) -> &'static str {
+        match self {
+            Self::Win => "stats-wins",
+            Self::Wall => "stats-lost-to-wall",
+            Self::OwnBody => "stats-lost-to-own-body",
+            Self::Obstacle => "stats-lost-to-obstacle",
+            Self::Timeout => "stats-out-of-time"
+        }
+    }
+
+    pub fn get_color(&self) -> Color {
+        match self {
+            Self::Win => Color::GREEN,
+            Self::Wall => Color::MAROON,
+            Self::OwnBody => Color::ORANGE_RED,
+            Self::Obstacle => Color::PURPLE,
+            Self::Timeout => Color::BLUE
+        }
+    }
+}
+
+impl StatsRecord {
+    pub fn get_outcome_count(&self, outcome: Outcome) -> Sze {
+        match outcome {
+            Outcome::Win => self.wins,
+            Outcome::Wall => self.losses_to_wall,
+            Outcome::OwnBody => self.losses_to_own_body,
+            Outcome::Obstacle => self.losses_to_obstacle,
+            Outcome::Timeout => self.timeouts
+        }
+    }
+
+    pub fn get_best_score(&self, difficulty: Difficulty) -> Sze {
+        self.best_scores.get(&difficulty).copied().unwrap_or(0)
+    }
+
+    pub fn get_average_survival_ticks(&self) -> u64 {
+        self.total_survival_ticks.checked_div(self.games_played as u64).unwrap_or(0)
+    }
+
+    fn add_play_time(&mut self, delta: Duration, is_boosting: bool) {
+        self.time_played += delta;
+        if is_boosting {
+            self.time_boosting += delta;
+        }
+    }
+
+    fn add_food(&mut self, snake_length: Sze) {
+        self.total_food += 1;
+        self.longest_snake = self.longest_snake.max(snake_length);
+    }
+
+    fn add_game(&mut self, outcome: Outcome, sim: &Sim) {
+        self.games_played += 1;
+        *match outcome {
+            Outcome::Win => &mut self.wins,
+            Outcome::Wall => &mut self.losses_to_wall,
+            Outcome::OwnBody => &mut self.losses_to_own_body,
+            Outcome::Obstacle => &mut self.losses_to_obstacle,
+            Outcome::Timeout => &mut self.timeouts
+        } += 1;
+        self.best_score = self.best_score.max(sim.get_score());
+        let best_score = self.best_scores.entry(sim.get_difficulty()).or_insert(0);
+        *best_score = (*best_score).max(sim.get_score());
+        self.longest_snake = self.longest_snake.max(sim.get_snake_length());
+        self.total_survival_ticks += sim.get_tick() as u64;
+        if self.recent_scores.len() == RECENT_SCORES_LEN {
+            self.recent_scores.remove(0);
+        }
+        self.recent_scores.push(sim.get_score());
+    }
+}
+
+impl Stats {
+    /// Starts from zero if there are no saved stats or they can't be read
+    pub fn load() -> Self {
+        let mut lifetime: StatsRecord = match fs::read_to_string(STATS_PATH) {
+            Ok(contents) => ron::from_str(&contents).unwrap_or_else(| error | {
+                eprintln!("Ignoring unreadable stats in {}: {}", STATS_PATH, error);
+                StatsRecord::default()
+            }),
+            Err(_) => StatsRecord::default()
+        };
+        // Stats from before difficulties were all played on Normal
+        if lifetime.best_scores.is_empty() && lifetime.best_score > 0 {
+            lifetime.best_scores.insert(Difficulty::Normal, lifetime.best_score);
+        }
+        Stats { session: StatsRecord::default(), lifetime }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let contents = ron::ser::to_string_pretty(&self.lifetime, ron::ser::PrettyConfig::default())
+            .map_err(| error | io::Error::new(io::ErrorKind::InvalidData, error))?;
+        fs::create_dir_all(STATS_DIR)?;
+        fs::write(STATS_PATH, contents)
+    }
+
+    pub fn add_play_time(&mut self, delta: Duration, is_boosting: bool) {
+        self.session.add_play_time(delta, is_boosting);
+        self.lifetime.add_play_time(delta, is_boosting);
+    }
+
+    pub fn add_food(&mut self, snake_length: Sze) {
+        self.session.add_food(snake_length);
+        self.lifetime.add_food(snake_length);
+    }
+
+    pub fn add_game(&mut self, game_over: SimulationOver, sim: &Sim) {
+        if !sim.is_score_ranked() {
+            return;
+        }
+        let outcome = match game_over.reason {
+            EndReason::BoardFull => Outcome::Win,
+            EndReason::HitWall => Outcome::Wall,
+            EndReason::BitSelf => Outcome::OwnBody,
+            EndReason::HitObstacle => Outcome::Obstacle,
+            EndReason::Timeout => Outcome::Timeout
+        };
+        self.session.add_game(outcome, sim);
+        self.lifetime.add_game(outcome, sim);
+    }
+
+    /// Session and lifetime values side by side, the label column is as wide as the longest label
+    pub fn get_table(&self, locale: &Locale) -> String {
+        let mut rows: Vec<(String, String, String)> = vec!(
+            (locale.get("stats-games-played"), self.session.games_played.to_string(), self.lifetime.games_played.to_string()),
+            (locale.get("stats-wins"), self.session.wins.to_string(), self.lifetime.wins.to_string()),
+            (locale.get("stats-lost-to-wall"), self.session.losses_to_wall.to_string(), self.lifetime.losses_to_wall.to_string()),
+            (
+                locale.get("stats-lost-to-own-body"),
+                self.session.losses_to_own_body.to_string(),
+                self.lifetime.losses_to_own_body.to_string()
+            ),
+            (
+                locale.get("stats-lost-to-obstacle"),
+                self.session.losses_to_obstacle.to_string(),
+                self.lifetime.losses_to_obstacle.to_string()
+            ),
+            (locale.get("stats-out-of-time"), self.session.timeouts.to_string(), self.lifetime.timeouts.to_string()),
+            (locale.get("stats-food-eaten"), self.session.total_food.to_string(), self.lifetime.total_food.to_string()),
+            (locale.get("stats-longest-snake"), self.session.longest_snake.to_string(), self.lifetime.longest_snake.to_string()),
+            (
+                locale.get("stats-average-ticks"),
+                self.session.get_average_survival_ticks().to_string(),
+                self.lifetime.get_average_survival_ticks().to_string()
+            ),
+            (locale.get("stats-time-boosting"), format_duration(self.session.time_boosting), format_duration(self.lifetime.time_boosting)),
+            (locale.get("stats-time-played"), format_duration(self.session.time_played), format_duration(self.lifetime.time_played))
+        );
+        rows.splice(8..8, DIFFICULTIES.iter().map(| &difficulty | (
+            locale.format("stats-best-score", &[("difficulty", locale.get(difficulty.get_message_id()).into())]),
+            self.session.get_best_score(difficulty).to_string(),
+            self.lifetime.get_best_score(difficulty).to_string()
+        )));
+        let label_width = rows.iter().map(| (label, _, _) | label.chars().count()).max().unwrap_or(0) + 2;
+        let mut table = format!(
+            "{:<label_width$}{:>10}{:>10}\n", "", locale.get("stats-session"), locale.get("stats-lifetime")
+        );
+        for (label, session, lifetime) in rows {
+            table += &format!("{:<label_width$}{:>10}{:>10}\n", label, session, lifetime);
+        }
+        table
+    }
+}
+
+/// `h:mm:ss`
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
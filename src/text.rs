@@ -1,12 +1,22 @@
 use bevy::{
     sprite::Anchor,
     ecs::entity::Entity,
-    prelude::{Commands, Resource, AssetServer, Res, Color, Transform, Font, Handle},
+    prelude::{Commands, Resource, Color, Transform},
     text::{Text2dBundle, Text, TextAlignment, BreakLineOn, TextSection, TextStyle}
 };
 
-use crate::{render::get_score_transform, input::PAUSE_GAME_KEY};
-use crate::input::{RESTART_GAME_KEY, START_GAME_KEY, BOOST_GAME_KEY};
+use crate::{Sze, SimulationOver, simulation::EndReason, save::SavedGame, daily::DailySession, stats::{Stats, OUTCOMES, RECENT_SCORES_LEN}, editor::{LevelEditor, EDITOR_TOOLS}, menu::PauseMenu, settings::Settings, locale::Locale};
+use crate::achievements::{AchievementTracker, ACHIEVEMENTS};
+use crate::render::{
+    get_score_transform, get_timer_transform, get_effects_transform, get_editor_text_transform, get_split_transform,
+    get_speed_transform, get_stats_text_transform, get_toast_transform
+};
+use crate::input::{
+    RESTART_GAME_KEY, START_GAME_KEY, PAUSE_GAME_KEY, DAILY_PRACTICE_KEY, CONTINUE_GAME_KEY, STATS_KEY, ACHIEVEMENTS_KEY, LANGUAGE_KEY,
+    MENU_UP_KEY, MENU_DOWN_KEY, MENU_SELECT_KEY,
+    EDITOR_KEY, EDITOR_SAVE_KEY, EDITOR_LOAD_KEY, EDITOR_GROW_KEY, EDITOR_SHRINK_KEY,
+    CommandKey, get_controls_description
+};
 use crate::simulation::START_SNAKE_LENGHT;
 
 #[derive(Resource)]
@@ -15,11 +25,20 @@ pub struct SnakeTexts {
     pub game_over: Entity,
     pub start: Entity,
     pub pause: Entity,
+    pub countdown: Entity,
+    pub effects: Entity,
+    pub timer: Entity,
+    pub split: Entity,
+    pub speed: Entity,
+    pub editor: Entity,
+    pub stats: Entity,
+    pub achievements: Entity,
+    pub toast: Entity,
 }
 
 impl SnakeTexts {
-    pub fn initialize(&mut self, mut commands: Commands, asset_server: Res<AssetServer>) {
-        let font: Handle<Font> = asset_server.load("fonts/FiraMono-Medium.ttf");
+    pub fn initialize(&mut self, commands: &mut Commands, locale: &Locale, daily: &DailySession) {
+        let font = locale.get_font();
         self.score = commands.spawn(Text2dBundle {
             text: Text {
                 sections: vec!(TextSection {
@@ -37,15 +56,102 @@ impl SnakeTexts {
             text_anchor: Anchor::BottomRight,
             ..Default::default()
         }).id();
+        self.timer = commands.spawn(Text2dBundle {
+            text: Text {
+                sections: vec!(TextSection {
+                    value: String::new(),
+                    style: TextStyle {
+                        font: font.clone(),
+                        font_size: 30.0,
+                        color: Color::BLACK,
+                    },
+                }),
+                alignment: TextAlignment::Center,
+                linebreak_behavior: BreakLineOn::AnyCharacter,
+            },
+            transform: get_timer_transform(),
+            text_anchor: Anchor::BottomCenter,
+            ..Default::default()
+        }).id();
+        self.split = commands.spawn(Text2dBundle {
+            text: Text {
+                sections: vec!(TextSection {
+                    value: String::new(),
+                    style: TextStyle {
+                        font: font.clone(),
+                        font_size: 24.0,
+                        color: Color::BLACK,
+                    },
+                }),
+                alignment: TextAlignment::Center,
+                linebreak_behavior: BreakLineOn::AnyCharacter,
+            },
+            transform: get_split_transform(),
+            text_anchor: Anchor::TopCenter,
+            ..Default::default()
+        }).id();
+        self.speed = commands.spawn(Text2dBundle {
+            text: Text {
+                sections: vec!(TextSection {
+                    value: String::new(),
+                    style: TextStyle {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        color: Color::BLACK,
+                    },
+                }),
+                alignment: TextAlignment::Left,
+                linebreak_behavior: BreakLineOn::AnyCharacter,
+            },
+            transform: get_speed_transform(),
+            text_anchor: Anchor::TopLeft,
+            ..Default::default()
+        }).id();
+        self.effects = commands.spawn(Text2dBundle {
+            text: Text {
+                sections: vec!(TextSection {
+                    value: String::new(),
+                    style: TextStyle {
+                        font,
+                        font_size: 18.0,
+                        color: Color::BLACK,
+                    },
+                }),
+                alignment: TextAlignment::Left,
+                linebreak_behavior: BreakLineOn::AnyCharacter,
+            },
+            transform: get_effects_transform(),
+            text_anchor: Anchor::BottomLeft,
+            ..Default::default()
+        }).id();
+        self.spawn_start_menu(commands, locale, daily);
+    }
 
-        self.game_over = commands.spawn(Text2dBundle {
+    pub fn spawn_start_menu(&mut self, commands: &mut Commands, locale: &Locale, daily: &DailySession) {
+        let font = locale.get_font();
+        let continue_line = if SavedGame::exists() {
+            format!("{}\n", locale.format("start-continue", &[("key", CONTINUE_GAME_KEY.str.into())]))
+        } else {
+            String::new()
+        };
+        let press = | id: &str, key: &CommandKey | locale.format(id, &[("key", key.str.into())]);
+        self.start = commands.spawn(Text2dBundle {
             text: Text {
                 sections: vec!(TextSection {
                     value: format!(
-                        "\nMove with arrows keys\nPress {} for boost\nPress {} to start\nPress {} to pause",
-                        BOOST_GAME_KEY.str,
-                        START_GAME_KEY.str,
-                        PAUSE_GAME_KEY.str
+                        "\n{}\n{}{}\n{}\n{}\n{}\n{}\n{}\n{}",
+                        get_controls_description(locale),
+                        continue_line,
+                        press("start-new-game", &START_GAME_KEY),
+                        daily.get_menu_description(locale),
+                        press("start-daily-practice", &DAILY_PRACTICE_KEY),
+                        press("start-editor", &EDITOR_KEY),
+                        press("start-stats", &STATS_KEY),
+                        press("start-achievements", &ACHIEVEMENTS_KEY),
+                        locale.format("start-language", &[
+                            ("key", LANGUAGE_KEY.str.into()),
+                            ("language", locale.get("language-name").into())
+                        ])
                     ),
                     style: TextStyle {
                         font,
@@ -62,27 +168,101 @@ impl SnakeTexts {
         }).id()
     }
 
-    pub fn despawn_start_menu(&mut self, mut commands: Commands) {
-        if self.game_over != Entity::PLACEHOLDER {
-            commands.entity(self.game_over).despawn();
+    pub fn despawn_start_menu(&mut self, commands: &mut Commands) {
+        if self.start != Entity::PLACEHOLDER {
+            commands.entity(self.start).despawn();
+            self.start = Entity::PLACEHOLDER;
         }
     }
 
-    pub fn spawn_paused_text(&mut self, mut commands: Commands, asset_server: Res<AssetServer>) {
-        let font: Handle<Font> = asset_server.load("fonts/FiraMono-Medium.ttf");
+    pub fn spawn_pause_menu(
+        &mut self,
+        commands: &mut Commands,
+        locale: &Locale,
+        pause_menu: &PauseMenu,
+        settings: &Settings
+    ) {
+        let font = locale.get_font();
+        let mut sections = vec!(TextSection {
+            value: pause_menu.get_title(locale),
+            style: TextStyle {
+                font: font.clone(),
+                font_size: 40.0,
+                color: Color::BLACK,
+            },
+        });
+        for (index, entry) in pause_menu.get_entries(settings, locale).into_iter().enumerate() {
+            let is_selected = index == pause_menu.get_selected();
+            sections.push(TextSection {
+                value: format!("{}{}\n", if is_selected { "> " } else { "" }, entry),
+                style: TextStyle {
+                    font: font.clone(),
+                    font_size: 25.0,
+                    color: if is_selected { Color::WHITE } else { Color::BLACK },
+                },
+            });
+        }
+        sections.push(TextSection {
+            value: locale.format("pause-hint", &[
+                ("up", MENU_UP_KEY.str.into()),
+                ("down", MENU_DOWN_KEY.str.into()),
+                ("select", MENU_SELECT_KEY.str.into()),
+                ("resume", PAUSE_GAME_KEY.str.into())
+            ]),
+            style: TextStyle {
+                font,
+                font_size: 16.0,
+                color: Color::BLACK,
+            },
+        });
         self.pause = commands.spawn(Text2dBundle {
+            text: Text {
+                sections,
+                alignment: TextAlignment::Center,
+                linebreak_behavior: BreakLineOn::WordBoundary,
+            },
+            transform: Transform::from_xyz(0.0, 0.0, 2.0),
+            text_anchor: Anchor::Center,
+            ..Default::default()
+        }).id()
+    }
+
+    pub fn despawn_pause_menu(&mut self, commands: &mut Commands) {
+        if self.pause != Entity::PLACEHOLDER {
+            commands.entity(self.pause).despawn();
+            self.pause = Entity::PLACEHOLDER;
+        }
+    }
+
+    /// The first section lists the controls, the second one is kept up to date with `LevelEditor::get_description`
+    pub fn spawn_editor_text(&mut self, commands: &mut Commands, locale: &Locale, editor: &LevelEditor) {
+        let font = locale.get_font();
+        let tools: Vec<String> = EDITOR_TOOLS
+            .iter()
+            .enumerate()
+            .map(| (index, tool) | format!("{} {}", index + 1, locale.get(tool.get_message_id())))
+            .collect();
+        self.editor = commands.spawn(Text2dBundle {
             text: Text {
                 sections: vec!(
                     TextSection {
-                        value: "Paused\n".to_string(),
+                        value: format!("{}\n\n", locale.format("editor-help", &[
+                            ("tools", tools.join(", ").into()),
+                            ("shrink", EDITOR_SHRINK_KEY.str.into()),
+                            ("grow", EDITOR_GROW_KEY.str.into()),
+                            ("save", EDITOR_SAVE_KEY.str.into()),
+                            ("load", EDITOR_LOAD_KEY.str.into()),
+                            ("play", MENU_SELECT_KEY.str.into()),
+                            ("back", EDITOR_KEY.str.into())
+                        ])),
                         style: TextStyle {
                             font: font.clone(),
-                            font_size: 50.0,
+                            font_size: 16.0,
                             color: Color::BLACK,
                         },
                     },
                     TextSection {
-                        value: format!("Press {} to unpause", PAUSE_GAME_KEY.str),
+                        value: editor.get_description(locale),
                         style: TextStyle {
                             font,
                             font_size: 20.0,
@@ -90,6 +270,151 @@ impl SnakeTexts {
                         },
                     }
                 ),
+                alignment: TextAlignment::Right,
+                linebreak_behavior: BreakLineOn::WordBoundary,
+            },
+            transform: get_editor_text_transform(),
+            text_anchor: Anchor::CenterRight,
+            ..Default::default()
+        }).id()
+    }
+
+    pub fn despawn_editor_text(&mut self, commands: &mut Commands) {
+        if self.editor != Entity::PLACEHOLDER {
+            commands.entity(self.editor).despawn();
+            self.editor = Entity::PLACEHOLDER;
+        }
+    }
+
+    /// The table on the left, the legend of the charts `render_stats` draws on the right under it
+    pub fn spawn_stats_screen(&mut self, commands: &mut Commands, locale: &Locale, stats: &Stats) {
+        let font = locale.get_font();
+        let style = | color: Color | TextStyle { font: font.clone(), font_size: 16.0, color };
+        let mut sections = vec!(
+            TextSection { value: stats.get_table(locale), style: style(Color::BLACK) },
+            TextSection {
+                value: format!("\n{}\n", locale.format("stats-charts", &[("count", RECENT_SCORES_LEN.into())])),
+                style: style(Color::BLACK)
+            }
+        );
+        sections.extend(OUTCOMES.iter().map(| outcome | TextSection {
+            value: format!("{} ({})\n", locale.get(outcome.get_message_id()), stats.lifetime.get_outcome_count(*outcome)),
+            style: style(outcome.get_color())
+        }));
+        sections.push(TextSection {
+            value: format!("\n{}", locale.format("go-back", &[("key", STATS_KEY.str.into()), ("select", MENU_SELECT_KEY.str.into())])),
+            style: style(Color::BLACK)
+        });
+        self.stats = commands.spawn(Text2dBundle {
+            text: Text {
+                sections,
+                alignment: TextAlignment::Left,
+                linebreak_behavior: BreakLineOn::AnyCharacter,
+            },
+            transform: get_stats_text_transform(),
+            text_anchor: Anchor::TopLeft,
+            ..Default::default()
+        }).id();
+    }
+
+    pub fn despawn_stats_screen(&mut self, commands: &mut Commands) {
+        if self.stats != Entity::PLACEHOLDER {
+            commands.entity(self.stats).despawn();
+            self.stats = Entity::PLACEHOLDER;
+        }
+    }
+
+    pub fn spawn_achievements_screen(&mut self, commands: &mut Commands, locale: &Locale, tracker: &AchievementTracker) {
+        let font = locale.get_font();
+        let style = | color: Color | TextStyle { font: font.clone(), font_size: 16.0, color };
+        let mut sections = vec!(TextSection {
+            value: format!("{}\n\n", locale.format("achievements-title", &[
+                ("unlocked", tracker.get_unlocked_count().into()),
+                ("total", ACHIEVEMENTS.len().into())
+            ])),
+            style: style(Color::BLACK)
+        });
+        sections.extend(ACHIEVEMENTS.iter().map(| achievement | {
+            let is_unlocked = tracker.is_unlocked(achievement);
+            TextSection {
+                value: format!(
+                    "[{}] {}\n    {}\n",
+                    if is_unlocked { "x" } else { " " },
+                    achievement.get_name(locale),
+                    achievement.get_description(locale)
+                ),
+                style: style(if is_unlocked { Color::DARK_GREEN } else { Color::GRAY })
+            }
+        }));
+        sections.push(TextSection {
+            value: format!("\n{}", locale.format("go-back", &[
+                ("key", ACHIEVEMENTS_KEY.str.into()),
+                ("select", MENU_SELECT_KEY.str.into())
+            ])),
+            style: style(Color::BLACK)
+        });
+        self.achievements = commands.spawn(Text2dBundle {
+            text: Text {
+                sections,
+                alignment: TextAlignment::Left,
+                linebreak_behavior: BreakLineOn::AnyCharacter,
+            },
+            transform: get_stats_text_transform(),
+            text_anchor: Anchor::TopLeft,
+            ..Default::default()
+        }).id();
+    }
+
+    pub fn despawn_achievements_screen(&mut self, commands: &mut Commands) {
+        if self.achievements != Entity::PLACEHOLDER {
+            commands.entity(self.achievements).despawn();
+            self.achievements = Entity::PLACEHOLDER;
+        }
+    }
+
+    /// `message` is a title line followed by details
+    pub fn spawn_toast(&mut self, commands: &mut Commands, locale: &Locale, message: String) {
+        let font = locale.get_font();
+        self.toast = commands.spawn(Text2dBundle {
+            text: Text {
+                sections: vec!(TextSection {
+                    value: message,
+                    style: TextStyle {
+                        font,
+                        font_size: 20.0,
+                        color: Color::DARK_GREEN,
+                    },
+                }),
+                alignment: TextAlignment::Center,
+                linebreak_behavior: BreakLineOn::AnyCharacter,
+            },
+            transform: get_toast_transform(),
+            text_anchor: Anchor::BottomCenter,
+            ..Default::default()
+        }).id();
+    }
+
+    pub fn despawn_toast(&mut self, commands: &mut Commands) {
+        if self.toast != Entity::PLACEHOLDER {
+            commands.entity(self.toast).despawn();
+            self.toast = Entity::PLACEHOLDER;
+        }
+    }
+
+    pub fn is_toast_shown(&self) -> bool { self.toast != Entity::PLACEHOLDER }
+
+    pub fn spawn_countdown_text(&mut self, commands: &mut Commands, locale: &Locale, remaining: Sze) {
+        let font = locale.get_font();
+        self.countdown = commands.spawn(Text2dBundle {
+            text: Text {
+                sections: vec!(TextSection {
+                    value: remaining.to_string(),
+                    style: TextStyle {
+                        font,
+                        font_size: 80.0,
+                        color: Color::BLACK,
+                    },
+                }),
                 alignment: TextAlignment::Center,
                 linebreak_behavior: BreakLineOn::WordBoundary,
             },
@@ -99,19 +424,27 @@ impl SnakeTexts {
         }).id()
     }
 
-    pub fn despawn_paused_text(&mut self, mut commands: Commands) {
-        if self.game_over != Entity::PLACEHOLDER {
-            commands.entity(self.pause).despawn();
+    pub fn despawn_countdown_text(&mut self, commands: &mut Commands) {
+        if self.countdown != Entity::PLACEHOLDER {
+            commands.entity(self.countdown).despawn();
+            self.countdown = Entity::PLACEHOLDER;
         }
     }
 
-    pub fn spawn_game_over_text(&mut self, mut commands: Commands, asset_server: Res<AssetServer>, is_win: bool) {
-        let font: Handle<Font> = asset_server.load("fonts/FiraMono-Medium.ttf");
+    pub fn spawn_game_over_text(
+        &mut self,
+        commands: &mut Commands,
+        locale: &Locale,
+        game_over: SimulationOver,
+        is_score_ranked: bool
+    ) {
+        let unranked_line = if is_score_ranked { String::new() } else { format!("\n{}", locale.get("game-over-unranked")) };
+        let font = locale.get_font();
         self.game_over = commands.spawn(Text2dBundle {
             text: Text {
                 sections: vec!(
                     TextSection {
-                        value: "GAME OVER\n".to_string(),
+                        value: format!("{}\n", locale.get("game-over")),
                         style: TextStyle {
                             font: font.clone(),
                             font_size: 50.0,
@@ -119,7 +452,11 @@ impl SnakeTexts {
                         },
                     },
                     TextSection {
-                        value: (if is_win {"WIN"} else {"LOSE"}).to_string(),
+                        value: locale.get(match game_over.reason {
+                            EndReason::BoardFull => "result-win",
+                            EndReason::Timeout => "result-time-up",
+                            _ => "result-lose"
+                        }),
                         style: TextStyle {
                             font: font.clone(),
                             font_size: 40.0,
@@ -127,7 +464,16 @@ impl SnakeTexts {
                         },
                     },
                     TextSection {
-                        value: format!("\nPress {} to restart\nPress ESC to exit", RESTART_GAME_KEY.str),
+                        value: format!(
+                            "\n{}{}\n{}\n{}",
+                            locale.format("game-over-reason", &[
+                                ("reason", locale.get(game_over.reason.get_message_id()).into()),
+                                ("tick", game_over.tick.into())
+                            ]),
+                            unranked_line,
+                            locale.format("game-over-restart", &[("key", RESTART_GAME_KEY.str.into())]),
+                            locale.format("game-over-exit", &[("key", "ESC".into())])
+                        ),
                         style: TextStyle {
                             font,
                             font_size: 20.0,
@@ -144,9 +490,10 @@ impl SnakeTexts {
         }).id();
     }
 
-    pub fn despawn_game_over_text(&mut self, mut commands: Commands) {
+    pub fn despawn_game_over_text(&mut self, commands: &mut Commands) {
         if self.game_over != Entity::PLACEHOLDER {
             commands.entity(self.game_over).despawn();
+            self.game_over = Entity::PLACEHOLDER;
         }
     }
 }
@@ -158,6 +505,15 @@ impl Default for SnakeTexts {
             game_over: Entity::PLACEHOLDER,
             start: Entity::PLACEHOLDER,
             pause: Entity::PLACEHOLDER,
+            countdown: Entity::PLACEHOLDER,
+            effects: Entity::PLACEHOLDER,
+            timer: Entity::PLACEHOLDER,
+            split: Entity::PLACEHOLDER,
+            speed: Entity::PLACEHOLDER,
+            editor: Entity::PLACEHOLDER,
+            stats: Entity::PLACEHOLDER,
+            achievements: Entity::PLACEHOLDER,
+            toast: Entity::PLACEHOLDER,
         }
     }
 }
@@ -1,39 +1,238 @@
 use std::time::Duration;
 use::bevy::prelude::{Timer, TimerMode, Resource};
+use serde::{Serialize, Deserialize};
 
+use crate::{Sze, FoodEaten, food::FoodKind, difficulty::Difficulty};
+
+/// Period of the `FixedUpdate` schedule the simulation runs in
+pub const SIM_TIMESTEP_SECONDS: f64 = 1.0 / 120.0;
+/// Most frame time the simulation catches up on, anything older is dropped
+///
+/// Frames slower than this still play at wall time speed, only longer hitches like a dragged
+/// window stop adding steps.
+pub const MAX_CATCH_UP_SECONDS: f64 = 0.25;
+
+/// Tick interval at full boost, or half the interval of the speed level if that's shorter
+const BOOST_TICK_SECONDS: f32 = 0.08;
+/// Time boost takes to go from off to full and back
+const BOOST_RAMP_SECONDS: f32 = 0.3;
+/// A full stamina bar lasts this long while boosting
+const STAMINA_DRAIN_SECONDS: f32 = 2.5;
+/// An empty stamina bar fills up again in this long while not boosting
+const STAMINA_REFILL_SECONDS: f32 = 5.0;
+/// Once the bar runs empty boost stays off until it's refilled this far
+const MIN_STAMINA_TO_BOOST: f32 = 0.25;
+/// Boost still counts while it ramps down, so it can last this long after the bar runs empty
+const EXHAUSTED_BOOST_SECONDS: f32 = BOOST_RAMP_SECONDS;
+
+/// Decides when the simulation steps
+///
+/// There is a single tick interval, the one the difficulty gives the current speed level pulled
+/// towards the boost interval as the boost ramps up. Boosting drains stamina, which refills while it's off.
 #[derive(Resource)]
 pub struct StepTimers {
-    pub boost_timer: Timer,
-    pub tick_timer: Timer
+    difficulty: Difficulty,
+    speed: SpeedState,
+    /// 0 is off, 1 is full boost
+    boost: f32,
+    /// 0 is empty, 1 is full
+    stamina: f32,
+    is_exhausted: bool,
+    /// Fraction of the current tick that has passed
+    tick_progress: f32
+}
+
+/// Where a game is on its speed curve, kept along with rewind snapshots and saves
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct SpeedState {
+    pub level: Sze,
+    /// Levels the curve handed out, speed and slow food move `level` on top of these
+    pub earned_levels: Sze
 }
 
 impl StepTimers {
-    pub fn increase_tick_speed(&mut self) {
-        let min_duration = Duration::from_secs_f32(0.1);
-        let new_duration = self.tick_timer.duration() - Duration::from_secs_f32(0.06);
-        if new_duration > min_duration {
-            self.tick_timer.set_duration(new_duration)
+    /// Moves up the speed curve when a level is earned and for speed food, down for slow food
+    pub fn on_food_eaten(&mut self, event: &FoodEaten) {
+        let preset = self.difficulty.get_preset();
+        let earned_levels = preset.get_earned_levels(event.pieces_eaten, event.new_score);
+        let mut level = self.speed.level + earned_levels.saturating_sub(self.speed.earned_levels);
+        match event.food_kind {
+            FoodKind::Speed => level += 1,
+            FoodKind::Slow => level = level.saturating_sub(1),
+            _ => {}
         }
+        self.speed = SpeedState { level: level.min(preset.get_max_level()), earned_levels };
+    }
+
+    /// Back to the starting speed of `difficulty` with no boost and a full stamina bar
+    pub fn reset_tick_speed(&mut self, difficulty: Difficulty) {
+        *self = StepTimers { difficulty, ..StepTimers::default() };
+    }
+
+    pub fn get_difficulty(&self) -> Difficulty { self.difficulty }
+
+    pub fn get_speed(&self) -> SpeedState { self.speed }
+
+    /// Restores a speed read from `get_speed`, clamped to the levels the difficulty has
+    pub fn set_speed(&mut self, speed: SpeedState) {
+        let max_level = self.difficulty.get_preset().get_max_level();
+        self.speed = SpeedState { level: speed.level.min(max_level), ..speed };
+    }
+
+    /// Tick interval of the speed level, without boost
+    pub fn get_tick_duration(&self) -> Duration { self.difficulty.get_preset().get_tick_duration(self.speed.level) }
+
+    pub fn get_effective_tick_duration(&self) -> Duration {
+        let level_seconds = self.get_tick_duration().as_secs_f32();
+        let boost_seconds = BOOST_TICK_SECONDS.min(level_seconds / 2.0);
+        Duration::from_secs_f32(level_seconds + (boost_seconds - level_seconds) * self.boost)
     }
 
-    pub fn reset_tick_speed(&mut self) {
-        self.tick_timer.set_duration(Duration::from_secs_f32(Self::default_tick_time()));
+    pub fn is_boosting(&self) -> bool { self.boost > 0.0 }
+
+    pub fn get_stamina(&self) -> f32 { self.stamina }
+
+    /// True from the moment stamina runs out until it's refilled to `MIN_STAMINA_TO_BOOST`
+    pub fn is_exhausted(&self) -> bool { self.is_exhausted }
+
+    /// Moves the boost and stamina along and returns how many steps are due
+    ///
+    /// Time left over past the last due step carries over to the next call.
+    pub fn advance(&mut self, delta: Duration, is_boost_held: bool) -> u32 {
+        let seconds = delta.as_secs_f32();
+        let is_boost_wanted = is_boost_held && !self.is_exhausted;
+        let boost_change = seconds / BOOST_RAMP_SECONDS;
+        self.boost = if is_boost_wanted {
+            (self.boost + boost_change).min(1.0)
+        } else {
+            (self.boost - boost_change).max(0.0)
+        };
+        if self.is_boosting() {
+            self.stamina = (self.stamina - seconds / STAMINA_DRAIN_SECONDS).max(0.0);
+            self.is_exhausted |= self.stamina == 0.0;
+        } else {
+            self.stamina = (self.stamina + seconds / STAMINA_REFILL_SECONDS).min(1.0);
+            self.is_exhausted &= self.stamina < MIN_STAMINA_TO_BOOST;
+        }
+
+        self.tick_progress += seconds / self.get_effective_tick_duration().as_secs_f32();
+        let steps = self.tick_progress.floor();
+        self.tick_progress -= steps;
+        steps as u32
     }
 
-    fn default_tick_time() -> f32 { 0.5 }
+    /// Starts the current tick over
+    pub fn reset_tick_progress(&mut self) { self.tick_progress = 0.0; }
 }
 
 impl Default for StepTimers {
     fn default() -> Self {
         StepTimers{
-            boost_timer: Timer::from_seconds(
-                0.08,
-                TimerMode::Repeating
-            ),
-            tick_timer: Timer::from_seconds(
-                Self::default_tick_time(),
-                TimerMode::Repeating
-            )
+            difficulty: Difficulty::default(),
+            speed: SpeedState::default(),
+            boost: 0.0,
+            stamina: 1.0,
+            is_exhausted: false,
+            tick_progress: 0.0
+        }
+    }
+}
+
+/// Follows the stamina bar along the ticks of a replay, to catch boosts no bar could have paid for
+///
+/// Replays only know ticks, so a boosted tick drains the bar as if it took the shortest time a
+/// tick can take and any other tick refills it as if it took the longest. A boost this lets
+/// through may still have been impossible, but one it turns down always was.
+pub struct StaminaCheck {
+    timers: StepTimers,
+    /// Time spent boosting on an empty bar, allowed while the boost ramps down
+    exhausted_seconds: f32
+}
+
+impl StaminaCheck {
+    pub fn new(difficulty: Difficulty) -> Self {
+        let mut timers = StepTimers::default();
+        timers.reset_tick_speed(difficulty);
+        StaminaCheck { timers, exhausted_seconds: 0.0 }
+    }
+
+    pub fn on_food_eaten(&mut self, event: &FoodEaten) { self.timers.on_food_eaten(event); }
+
+    /// Accounts for a tick, returns false if the bar couldn't have lasted through it
+    pub fn on_tick(&mut self, is_boosting: bool) -> bool {
+        let timestep_seconds = SIM_TIMESTEP_SECONDS as f32;
+        let level_seconds = self.timers.get_tick_duration().as_secs_f32();
+        if is_boosting {
+            let shortest_seconds = (BOOST_TICK_SECONDS.min(level_seconds / 2.0) - timestep_seconds).max(0.0);
+            let stamina = self.timers.stamina - shortest_seconds / STAMINA_DRAIN_SECONDS;
+            if stamina < 0.0 {
+                self.exhausted_seconds += -stamina * STAMINA_DRAIN_SECONDS;
+            }
+            self.timers.stamina = stamina.max(0.0);
+        } else {
+            let longest_seconds = level_seconds + timestep_seconds;
+            self.timers.stamina = (self.timers.stamina + longest_seconds / STAMINA_REFILL_SECONDS).min(1.0);
+            self.exhausted_seconds = 0.0;
         }
+        self.exhausted_seconds <= EXHAUSTED_BOOST_SECONDS
+    }
+}
+
+const RESUME_COUNTDOWN_SECONDS: Sze = 3;
+
+#[derive(Resource)]
+pub struct ResumeCountdown {
+    timer: Timer,
+    remaining: Sze
+}
+
+impl ResumeCountdown {
+    pub fn start(&mut self) {
+        self.timer.reset();
+        self.remaining = RESUME_COUNTDOWN_SECONDS;
+    }
+
+    /// Returns true whenever a full second has passed and the remaining count changed
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        if self.timer.tick(delta).just_finished() {
+            self.remaining = self.remaining.saturating_sub(1);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_remaining(&self) -> Sze { self.remaining }
+
+    pub fn is_finished(&self) -> bool { self.remaining == 0 }
+}
+
+impl Default for ResumeCountdown {
+    fn default() -> Self {
+        ResumeCountdown {
+            timer: Timer::from_seconds(1.0, TimerMode::Repeating),
+            remaining: RESUME_COUNTDOWN_SECONDS
+        }
+    }
+}
+
+const TOAST_SECONDS: f32 = 3.0;
+
+/// Keeps an achievement toast up for `TOAST_SECONDS`
+#[derive(Resource)]
+pub struct ToastTimer {
+    timer: Timer
+}
+
+impl ToastTimer {
+    pub fn start(&mut self) { self.timer.reset(); }
+
+    /// Returns true once the toast has been up long enough
+    pub fn tick(&mut self, delta: Duration) -> bool { self.timer.tick(delta).just_finished() }
+}
+
+impl Default for ToastTimer {
+    fn default() -> Self {
+        ToastTimer { timer: Timer::from_seconds(TOAST_SECONDS, TimerMode::Once) }
     }
 }
@@ -1,9 +1,12 @@
+use serde::{Serialize, Deserialize};
 
-pub fn min<T: PartialOrd>(a: T, b: T) -> T { if a <= b { a } else { b } }
-
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Direction { Left, Right, Up, Down }
 
+pub const DIRECTIONS: [Direction; 4] = [Direction::Left, Direction::Right, Direction::Up, Direction::Down];
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
 impl Direction {
     pub fn opposite(&self) -> Self {
         match self {
@@ -14,3 +17,8 @@ impl Direction {
         }
     }
 }
+
+/// FNV-1a, unlike the std hashers it is the same on every build
+pub fn get_stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, | hash, &byte | (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}